@@ -1,23 +1,50 @@
 #![allow(unused_imports)]
 
+mod api_key;
 mod build;
+mod data_export;
 mod email;
+mod former_handle;
 mod handle;
+mod page;
+mod password;
 mod phone;
+mod phone_verification;
+mod session;
+mod token;
+mod totp;
 mod user;
+mod user_token;
+mod viewer;
 
+pub use api_key::*;
 pub use build::*;
+pub use data_export::*;
 pub use email::*;
+pub use former_handle::*;
 pub use handle::*;
+pub use page::*;
+pub use password::*;
 pub use phone::*;
+pub use phone_verification::*;
+pub use session::*;
+pub use token::*;
+pub use totp::*;
 pub use user::*;
+pub use user_token::*;
+pub use viewer::*;
 
 use super::*;
 
-use services::Services;
+use services::{Event, Services};
 
 pub type Context<T = Services> = EntityContext<T>;
 
+/// A value that can be displayed with its sensitive parts obscured.
+pub trait Mask: Display {
+    fn masked(&self) -> String;
+}
+
 use entrust::Record;
 use entrust::{AggregateOneQuery, AggregateQuery, MaybeAggregateOneQuery};
 use entrust::{Comparison, SortingDirection};
@@ -27,6 +54,16 @@ use entrust::{Entity, EntityContext, EntityId, EntityServices};
 use entrust::{FindOneQuery, FindQuery, MaybeFindOneQuery};
 use entrust::{Object, ObjectId};
 
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::Collection;
+
 use ::bson::DateTime as BsonDateTime;
 use ::bson::{bson, doc, from_document, to_document};
 use ::bson::{Bson, Document};
+
+/// Returns the collection that stores entities of type `T`, for atomic
+/// operations that can't be expressed as `entrust` queries.
+fn collection<T: Entity>(ctx: &Context) -> Collection<Record<T>> {
+    let name = collection_name(T::NAME);
+    ctx.services().database().collection(&name)
+}
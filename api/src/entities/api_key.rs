@@ -0,0 +1,127 @@
+use super::*;
+
+pub type ApiKeyId = EntityId<ApiKey>;
+
+/// An `ApiKey` is a long-lived credential that lets scripts and integrations
+/// act on behalf of a `User`.
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Object)]
+pub struct ApiKey {
+    pub user: UserId,
+    pub name: String,
+
+    /// The first few characters of the key, for telling keys apart.
+    pub prefix: String,
+
+    pub key: TokenHash,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub last_used_at: Option<DateTime>,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub expires_at: Option<DateTime>,
+}
+
+impl Entity for ApiKey {
+    const NAME: &'static str = "ApiKey";
+
+    type Services = Services;
+    type Conditions = ApiKeyConditions;
+    type Sorting = EmptySorting;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+pub struct ApiKeyConditions {
+    #[builder(default, setter(into))]
+    pub user: Option<UserId>,
+
+    #[builder(default, setter(into))]
+    pub key: Option<TokenHash>,
+}
+
+impl EntityConditions for ApiKeyConditions {
+    fn into_document(self) -> Document {
+        let ApiKeyConditions { user, key } = self;
+        let mut doc = Document::new();
+
+        if let Some(user) = user {
+            doc.insert("user", user);
+        }
+        if let Some(key) = key {
+            doc.insert("key", key);
+        }
+
+        doc
+    }
+}
+
+impl ApiKey {
+    /// The marker that API keys begin with, which distinguishes them from
+    /// session tokens.
+    pub const KEY_PREFIX: &'static str = "key_";
+
+    const PREFIX_LEN: usize = 8;
+
+    /// Creates a new `ApiKey` for `user`, along with the plaintext key, which
+    /// can't be recovered afterwards.
+    pub fn new(
+        user: UserId,
+        name: String,
+        expires_at: Option<DateTime>,
+    ) -> (Self, String) {
+        let token = Token::generate();
+        let key = format!("{}{}", Self::KEY_PREFIX, token.as_str());
+        let api_key = ApiKey {
+            user,
+            name,
+            prefix: key[..(Self::KEY_PREFIX.len() + Self::PREFIX_LEN)]
+                .to_owned(),
+            key: token.hash(),
+            last_used_at: None,
+            expires_at,
+        };
+        (api_key, key)
+    }
+
+    /// Parses the `Token` out of a plaintext API key.
+    pub fn parse_key(key: &str) -> Option<Token> {
+        let token = key.strip_prefix(Self::KEY_PREFIX)?;
+        token.parse().ok()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at <= now(),
+            None => false,
+        }
+    }
+
+    pub fn find_by_key(token: &Token) -> FindOneQuery<Self> {
+        let conditions = ApiKeyConditions::builder().key(token.hash()).build();
+        ApiKey::find_one(conditions)
+    }
+
+    pub fn find_by_user(user: UserId) -> FindQuery<Self> {
+        let conditions = ApiKeyConditions::builder().user(user).build();
+        ApiKey::find(conditions)
+    }
+
+    /// Deletes every API key belonging to `user`.
+    pub async fn revoke_all(user: UserId, ctx: &Context) -> Result<()> {
+        let api_keys: Vec<Record<ApiKey>> = ApiKey::find_by_user(user)
+            .load(ctx)
+            .await
+            .context("failed to find API keys")?
+            .try_collect()
+            .await
+            .context("failed to load API keys")?;
+        for api_key in api_keys {
+            api_key
+                .delete(ctx)
+                .await
+                .context("failed to delete API key")?;
+        }
+        Ok(())
+    }
+}
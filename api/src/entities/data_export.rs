@@ -0,0 +1,142 @@
+use super::*;
+
+/// A `DataExport` is a snapshot of everything stored about a `User`, for
+/// handing over to them on request.
+///
+/// Secrets such as password hashes, token hashes, and two-factor secrets are
+/// left out.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataExport {
+    pub exported_at: DateTime,
+    pub user: UserExport,
+    pub former_handles: Vec<FormerHandleExport>,
+    pub sessions: Vec<SessionExport>,
+    pub api_keys: Vec<ApiKeyExport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserExport {
+    pub id: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub handle: Handle,
+    pub name: String,
+    pub email: Email,
+    pub email_verified_at: Option<DateTime>,
+    pub phone: Phone,
+    pub phone_verified_at: Option<DateTime>,
+    pub role: Role,
+    pub has_password: bool,
+    pub two_factor_enabled: bool,
+    pub deleted_at: Option<DateTime>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormerHandleExport {
+    pub handle: Handle,
+    pub reserved_until: DateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExport {
+    pub id: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyExport {
+    pub id: String,
+    pub created_at: DateTime,
+    pub name: String,
+    pub prefix: String,
+    pub last_used_at: Option<DateTime>,
+    pub expires_at: Option<DateTime>,
+}
+
+impl DataExport {
+    /// Assembles a `DataExport` for `user`.
+    pub async fn build(user: &Record<User>, ctx: &Context) -> Result<Self> {
+        let former_handles: Vec<Record<FormerHandle>> =
+            FormerHandle::find_by_user(user.id())
+                .load(ctx)
+                .await
+                .context("failed to find former handles")?
+                .try_collect()
+                .await
+                .context("failed to load former handles")?;
+        let sessions: Vec<Record<Session>> = Session::find_by_user(user.id())
+            .load(ctx)
+            .await
+            .context("failed to find sessions")?
+            .try_collect()
+            .await
+            .context("failed to load sessions")?;
+        let api_keys: Vec<Record<ApiKey>> = ApiKey::find_by_user(user.id())
+            .load(ctx)
+            .await
+            .context("failed to find API keys")?
+            .try_collect()
+            .await
+            .context("failed to load API keys")?;
+
+        let user_export = UserExport {
+            id: user.id().to_string(),
+            created_at: user.created_at(),
+            updated_at: user.updated_at(),
+            handle: user.handle.clone(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            email_verified_at: user.email_verified_at,
+            phone: user.phone.clone(),
+            phone_verified_at: user.phone_verified_at,
+            role: user.role,
+            has_password: user.password.is_some(),
+            two_factor_enabled: matches!(
+                &user.totp,
+                Some(totp) if totp.is_enabled()
+            ),
+            deleted_at: user.deleted_at,
+        };
+        let former_handles = former_handles
+            .into_iter()
+            .map(|former_handle| FormerHandleExport {
+                handle: former_handle.handle.clone(),
+                reserved_until: former_handle.reserved_until,
+            })
+            .collect();
+        let sessions = sessions
+            .into_iter()
+            .map(|session| SessionExport {
+                id: session.id().to_string(),
+                created_at: session.created_at(),
+                expires_at: session.expires_at,
+            })
+            .collect();
+        let api_keys = api_keys
+            .into_iter()
+            .map(|api_key| ApiKeyExport {
+                id: api_key.id().to_string(),
+                created_at: api_key.created_at(),
+                name: api_key.name.clone(),
+                prefix: api_key.prefix.clone(),
+                last_used_at: api_key.last_used_at,
+                expires_at: api_key.expires_at,
+            })
+            .collect();
+
+        let export = DataExport {
+            exported_at: now(),
+            user: user_export,
+            former_handles,
+            sessions,
+            api_keys,
+        };
+        Ok(export)
+    }
+}
@@ -27,6 +27,17 @@ impl Email {
     }
 }
 
+impl Mask for Email {
+    /// Obscures all but the first character of the local part, i.e.
+    /// `j***@example.com`.
+    fn masked(&self) -> String {
+        let (local, domain) =
+            self.0.split_once('@').unwrap_or((self.0.as_str(), ""));
+        let first = local.chars().next().unwrap_or('*');
+        format!("{}***@{}", first, domain)
+    }
+}
+
 impl AsRef<str> for Email {
     delegate! {
         to self.0 {
@@ -61,3 +72,18 @@ impl From<Email> for Bson {
         s.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_all_but_first_character() {
+        let email = Email("jane.doe@example.com".to_owned());
+        assert_eq!(email.masked(), "j***@example.com");
+
+        // The length of the local part isn't revealed either
+        let email = Email("j@example.com".to_owned());
+        assert_eq!(email.masked(), "j***@example.com");
+    }
+}
@@ -0,0 +1,142 @@
+use super::*;
+
+pub type FormerHandleId = EntityId<FormerHandle>;
+
+/// A `FormerHandle` is a `Handle` that a `User` has since changed away from.
+///
+/// It stays reserved for that user for a while, so that links built from it
+/// can be redirected rather than taken over by someone else.
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Object)]
+pub struct FormerHandle {
+    pub user: UserId,
+    pub handle: Handle,
+    pub reserved_until: DateTime,
+}
+
+impl Entity for FormerHandle {
+    const NAME: &'static str = "FormerHandle";
+
+    type Services = Services;
+    type Conditions = FormerHandleConditions;
+    type Sorting = EmptySorting;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+pub struct FormerHandleConditions {
+    #[builder(default, setter(into))]
+    pub user: Option<UserId>,
+
+    #[builder(default, setter(into))]
+    pub handle: Option<Handle>,
+
+    /// Match only former handles that are still reserved at this time.
+    #[builder(default, setter(into))]
+    pub reserved_at: Option<DateTime>,
+}
+
+impl EntityConditions for FormerHandleConditions {
+    fn into_document(self) -> Document {
+        let FormerHandleConditions {
+            user,
+            handle,
+            reserved_at,
+        } = self;
+        let mut doc = Document::new();
+
+        if let Some(user) = user {
+            doc.insert("user", user);
+        }
+        if let Some(handle) = handle {
+            doc.insert("handle", handle);
+        }
+        if let Some(reserved_at) = reserved_at {
+            let reserved_at = BsonDateTime::from_chrono(reserved_at);
+            doc.insert("reserved_until", doc! { "$gt": reserved_at });
+        }
+
+        doc
+    }
+}
+
+impl FormerHandle {
+    /// How long a former handle stays reserved after it is changed away
+    /// from.
+    pub fn reservation_period() -> Duration {
+        Duration::days(90)
+    }
+
+    /// Records that `user` has changed away from `handle`.
+    pub fn new(user: UserId, handle: Handle) -> Self {
+        FormerHandle {
+            user,
+            handle,
+            reserved_until: now() + Self::reservation_period(),
+        }
+    }
+
+    pub fn find_by_user(user: UserId) -> FindQuery<Self> {
+        let conditions = FormerHandleConditions::builder().user(user).build();
+        FormerHandle::find(conditions)
+    }
+
+    /// Loads the former handle matching `handle` that is still reserved, if
+    /// any.
+    ///
+    /// At most one user can hold a handle at a time, and a user's former
+    /// handle is released when they take it back, so there is at most one.
+    pub async fn find_reserved(
+        handle: Handle,
+        ctx: &Context,
+    ) -> Result<Option<Record<Self>>> {
+        let conditions = FormerHandleConditions::builder()
+            .handle(handle)
+            .reserved_at(now())
+            .build();
+        FormerHandle::find_one(conditions)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load former handle")
+    }
+
+    /// Deletes `user`'s record of `handle`, once they have taken it back.
+    pub async fn release(
+        user: UserId,
+        handle: Handle,
+        ctx: &Context,
+    ) -> Result<()> {
+        let conditions = FormerHandleConditions::builder()
+            .user(user)
+            .handle(handle)
+            .build();
+        FormerHandle::release_matching(conditions, ctx).await
+    }
+
+    /// Deletes every former handle belonging to `user`, releasing them for
+    /// others to take.
+    pub async fn release_all(user: UserId, ctx: &Context) -> Result<()> {
+        let conditions = FormerHandleConditions::builder().user(user).build();
+        FormerHandle::release_matching(conditions, ctx).await
+    }
+
+    async fn release_matching(
+        conditions: FormerHandleConditions,
+        ctx: &Context,
+    ) -> Result<()> {
+        let former_handles: Vec<Record<FormerHandle>> =
+            FormerHandle::find(conditions)
+                .load(ctx)
+                .await
+                .context("failed to find former handles")?
+                .try_collect()
+                .await
+                .context("failed to load former handles")?;
+        for former_handle in former_handles {
+            former_handle
+                .delete(ctx)
+                .await
+                .context("failed to delete former handle")?;
+        }
+        Ok(())
+    }
+}
@@ -18,15 +18,50 @@ impl<'de> Deserialize<'de> for Handle {
 }
 
 impl Handle {
+    /// Handles that are reserved by default, since they could be mistaken
+    /// for routes or for staff.
+    pub const RESERVED: &'static [&'static str] = &[
+        "about",
+        "account",
+        "accounts",
+        "admin",
+        "administrator",
+        "api",
+        "app",
+        "auth",
+        "blog",
+        "dashboard",
+        "docs",
+        "export",
+        "graphql",
+        "help",
+        "login",
+        "logout",
+        "me",
+        "mail",
+        "root",
+        "settings",
+        "signup",
+        "staff",
+        "status",
+        "support",
+        "system",
+        "user",
+        "users",
+        "www",
+    ];
+
+    /// Parses a `Handle`, ignoring case.
     pub fn new(s: &str) -> Result<Self> {
         lazy_static! {
             static ref REGEX: Regex =
                 Regex::new(r"^([a-z0-9]+-*)*[a-z0-9]$").unwrap();
         }
+        let s = s.to_lowercase();
         ensure!(s.len() >= 2, "too short");
         ensure!(s.len() <= 32, "too long");
-        ensure!(REGEX.is_match(s), "invalid characters");
-        let handle = Self(s.to_owned());
+        ensure!(REGEX.is_match(&s), "invalid characters");
+        let handle = Self(s);
         Ok(handle)
     }
 
@@ -59,3 +94,35 @@ impl From<Handle> for Bson {
         s.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercases() {
+        let handle = Handle::new("Ada-Lovelace").unwrap();
+        assert_eq!(handle.as_str(), "ada-lovelace");
+        assert_eq!(handle, Handle::new("ADA-LOVELACE").unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_handles() {
+        let handles = ["a", "-ada", "ada-", "ada_lovelace", "ada lovelace", ""];
+        for handle in handles {
+            assert!(Handle::new(handle).is_err(), "{:?} is valid", handle);
+        }
+        assert!(Handle::new(&"a".repeat(32)).is_ok());
+        assert!(Handle::new(&"a".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn reserved_handles_are_valid() {
+        // Otherwise they could never be matched, and so wouldn't reserve
+        // anything
+        for &reserved in Handle::RESERVED {
+            let handle = Handle::new(reserved).unwrap();
+            assert_eq!(handle.as_str(), reserved);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use super::*;
+
+use base32::Alphabet as Base32Alphabet;
+use base32::{decode as decode_base32, encode as encode_base32};
+
+const CURSOR_ALPHABET: Base32Alphabet =
+    Base32Alphabet::RFC4648 { padding: false };
+
+/// A `Cursor` marks a position in a sorted list of entities, by the key they
+/// are sorted on, the value of that key, and their ID (to break ties).
+///
+/// Cursors are opaque to clients, which receive them as encoded strings.
+/// Since clients can forge them, decoded cursors are validated before they
+/// make their way into a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    key: String,
+    value: Bson,
+    id: Bson,
+}
+
+impl Cursor {
+    /// Builds a `Cursor` pointing at `record`, within a list sorted on
+    /// `key`.
+    pub fn new<T: Entity>(record: &Record<T>, key: &str) -> Result<Self> {
+        let id: Bson = record.id().into();
+        let value = if key == "_id" {
+            id.clone()
+        } else {
+            let data = to_document::<T>(record)
+                .context("failed to serialize record")?;
+            data.get(key).cloned().unwrap_or(Bson::Null)
+        };
+        let cursor = Cursor {
+            key: key.to_owned(),
+            value,
+            id,
+        };
+        Ok(cursor)
+    }
+
+    pub fn encode(&self) -> String {
+        let Cursor { key, value, id } = self;
+        let doc = doc! { "s": key, "k": value, "i": id };
+        let mut bytes = Vec::new();
+        doc.to_writer(&mut bytes)
+            .expect("cursor should serialize to BSON");
+        encode_base32(CURSOR_ALPHABET, &bytes)
+    }
+
+    pub fn decode(s: &str) -> Result<Self> {
+        let bytes =
+            decode_base32(CURSOR_ALPHABET, s).context("bad encoding")?;
+        let doc = Document::from_reader(&mut bytes.as_slice())
+            .context("bad document")?;
+        let key = doc.get_str("s").context("missing sorting key")?;
+        let value = doc.get("k").cloned().context("missing key")?;
+        let id = doc.get("i").cloned().context("missing ID")?;
+
+        // Only allow plain values, which can't be mistaken for query
+        // operators or patterns
+        ensure!(is_plain_value(&value), "bad key");
+        ensure!(matches!(id, Bson::ObjectId(_)), "bad ID");
+
+        let cursor = Cursor {
+            key: key.to_owned(),
+            value,
+            id,
+        };
+        Ok(cursor)
+    }
+
+    /// Builds a filter that matches entities sorted strictly after this
+    /// cursor, on `key` in the given direction.
+    ///
+    /// Fails if this cursor was built for a list sorted on another key.
+    fn filter(&self, key: &str, ascending: bool) -> Result<Document> {
+        let Cursor {
+            key: cursor_key,
+            value,
+            id,
+        } = self;
+        ensure!(cursor_key == key, "cursor is for a different ordering");
+        let operator = if ascending { "$gt" } else { "$lt" };
+        if key == "_id" {
+            return Ok(doc! { "_id": { operator: id } });
+        }
+        let filter = doc! {
+            "$or": [
+                { key: { operator: value } },
+                { key: value, "_id": { operator: id } },
+            ]
+        };
+        Ok(filter)
+    }
+}
+
+/// Whether `value` is a scalar that only ever matches by equality or
+/// comparison when used in a filter.
+fn is_plain_value(value: &Bson) -> bool {
+    matches!(
+        value,
+        Bson::Null
+            | Bson::Boolean(_)
+            | Bson::Int32(_)
+            | Bson::Int64(_)
+            | Bson::Double(_)
+            | Bson::Decimal128(_)
+            | Bson::String(_)
+            | Bson::DateTime(_)
+            | Bson::ObjectId(_)
+    )
+}
+
+/// A `PageQuery` loads one page of entities matching `conditions`, ordered by
+/// `sorting` and bounded by cursors, in the manner of a Relay connection.
+///
+/// Only sortings on a single key are supported; the entity ID is used to
+/// break ties.
+#[derive(Builder)]
+pub struct PageQuery<T: Entity> {
+    #[builder(default, setter(into))]
+    pub conditions: Option<T::Conditions>,
+
+    #[builder(default, setter(into))]
+    pub sorting: Option<T::Sorting>,
+
+    #[builder(default, setter(into))]
+    pub after: Option<Cursor>,
+
+    #[builder(default, setter(into))]
+    pub before: Option<Cursor>,
+
+    #[builder(default, setter(into))]
+    pub first: Option<usize>,
+
+    #[builder(default, setter(into))]
+    pub last: Option<usize>,
+}
+
+/// A `Page` is a slice of entities loaded by a `PageQuery`.
+#[derive(Debug, Clone)]
+pub struct Page<T: Entity> {
+    pub records: Vec<(Cursor, Record<T>)>,
+    pub has_previous: bool,
+    pub has_next: bool,
+}
+
+impl<T> PageQuery<T>
+where
+    T: Entity,
+    T::Conditions: Clone,
+    T::Sorting: Clone,
+{
+    /// The number of entities loaded when neither `first` nor `last` is
+    /// given.
+    pub const DEFAULT_SIZE: usize = 20;
+
+    /// The most entities that may be loaded at once.
+    pub const MAX_SIZE: usize = 100;
+
+    pub async fn load(self, ctx: &Context<T::Services>) -> Result<Page<T>> {
+        let PageQuery {
+            conditions,
+            sorting,
+            after,
+            before,
+            first,
+            last,
+        } = self;
+        let (key, ascending) = sorting_key(sorting)?;
+
+        // Paginate backwards only when given `last` without `first`
+        let backwards = first.is_none() && last.is_some();
+        let limit = match (first, last) {
+            (Some(first), _) => first,
+            (None, Some(last)) => last,
+            (None, None) => Self::DEFAULT_SIZE,
+        };
+        ensure!(
+            limit <= Self::MAX_SIZE,
+            "cannot load more than {} entities at once",
+            Self::MAX_SIZE
+        );
+
+        // Build query
+        let mut filters = Vec::new();
+        if let Some(conditions) = conditions {
+            filters.push(conditions.into_document());
+        }
+        if let Some(after) = &after {
+            filters.push(after.filter(&key, ascending)?);
+        }
+        if let Some(before) = &before {
+            filters.push(before.filter(&key, !ascending)?);
+        }
+        let filter = match filters.len() {
+            0 => Document::new(),
+            1 => filters.pop().unwrap(),
+            _ => doc! { "$and": filters },
+        };
+        let direction = if ascending != backwards { 1 } else { -1 };
+        let mut sort = Document::new();
+        if key != "_id" {
+            sort.insert(key.as_str(), direction);
+        }
+        sort.insert("_id", direction);
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$sort": sort },
+            doc! { "$limit": (limit + 1) as i64 },
+        ];
+
+        // Load one extra record, to tell whether there are more
+        let mut records: Vec<Record<T>> = T::aggregate(pipeline)
+            .load(ctx)
+            .await
+            .context("failed to find records")?
+            .try_collect()
+            .await
+            .context("failed to load records")?;
+        let has_more = records.len() > limit;
+        records.truncate(limit);
+        if backwards {
+            records.reverse();
+        }
+
+        let (mut has_previous, has_next) = if backwards {
+            (has_more, before.is_some())
+        } else {
+            (after.is_some(), has_more)
+        };
+        if let (Some(_), Some(last)) = (first, last) {
+            if records.len() > last {
+                records.drain(..records.len() - last);
+                has_previous = true;
+            }
+        }
+
+        let records = records
+            .into_iter()
+            .map(|record| {
+                let cursor = Cursor::new(&record, &key)?;
+                Ok((cursor, record))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let page = Page {
+            records,
+            has_previous,
+            has_next,
+        };
+        Ok(page)
+    }
+
+    /// Counts all entities matching `conditions`, regardless of cursors.
+    pub async fn count(&self, ctx: &Context<T::Services>) -> Result<u64> {
+        let filter = match &self.conditions {
+            Some(conditions) => conditions.clone().into_document(),
+            None => Document::new(),
+        };
+        let pipeline =
+            vec![doc! { "$match": filter }, doc! { "$count": "count" }];
+        let result: Option<Document> = T::aggregate_one(pipeline)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to count records")?;
+        let count = match result {
+            Some(result) => match result.get("count") {
+                Some(Bson::Int32(count)) => *count as u64,
+                Some(Bson::Int64(count)) => *count as u64,
+                _ => bail!("bad count result"),
+            },
+            None => 0,
+        };
+        Ok(count)
+    }
+}
+
+/// Determines the key and direction that `sorting` orders on, defaulting to
+/// ascending IDs.
+fn sorting_key<S: EntitySorting>(sorting: Option<S>) -> Result<(String, bool)> {
+    let sorting = match sorting {
+        Some(sorting) => sorting.into_document(),
+        None => return Ok(("_id".to_owned(), true)),
+    };
+    ensure!(sorting.len() == 1, "sorting must be on a single key");
+    let (key, direction) = sorting.iter().next().unwrap();
+    let ascending = match direction {
+        Bson::Int32(direction) => *direction > 0,
+        Bson::Int64(direction) => *direction > 0,
+        _ => bail!("bad sorting direction"),
+    };
+    Ok((key.to_owned(), ascending))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_raw(doc: Document) -> String {
+        let mut bytes = Vec::new();
+        doc.to_writer(&mut bytes).unwrap();
+        encode_base32(CURSOR_ALPHABET, &bytes)
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let id = Bson::ObjectId(ObjectId::new());
+        let cursors = [
+            ("name", Bson::String("Ada Lovelace".to_owned())),
+            ("age", Bson::Int64(42)),
+            ("name", Bson::Null),
+            ("_id", id.clone()),
+        ];
+        for (key, value) in cursors {
+            let cursor = Cursor {
+                key: key.to_owned(),
+                value,
+                id: id.clone(),
+            };
+            let decoded = Cursor::decode(&cursor.encode()).unwrap();
+            assert_eq!(decoded, cursor);
+        }
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode("").is_err());
+
+        // Valid BSON, but missing the ID
+        let encoded = encode_raw(doc! { "s": "name", "k": "Ada Lovelace" });
+        assert!(Cursor::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn cursor_rejects_operators() {
+        let id = ObjectId::new();
+        let values = [
+            bson!({ "$ne": null }),
+            bson!([{ "$ne": null }]),
+            Bson::RegularExpression(::bson::Regex {
+                pattern: ".*".to_owned(),
+                options: String::new(),
+            }),
+        ];
+        for value in values {
+            let encoded = encode_raw(doc! { "s": "name", "k": value, "i": id });
+            assert!(Cursor::decode(&encoded).is_err());
+        }
+
+        // IDs must be ObjectIds
+        let encoded = encode_raw(doc! {
+            "s": "name",
+            "k": "Ada Lovelace",
+            "i": { "$ne": null },
+        });
+        assert!(Cursor::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn cursor_rejects_other_orderings() {
+        let cursor = Cursor {
+            key: "name".to_owned(),
+            value: Bson::String("Ada Lovelace".to_owned()),
+            id: Bson::ObjectId(ObjectId::new()),
+        };
+        assert!(cursor.filter("name", true).is_ok());
+        assert!(cursor.filter("_id", true).is_err());
+    }
+
+    #[test]
+    fn sorting_key_defaults_to_ascending_ids() {
+        let (key, ascending) = sorting_key::<UserSorting>(None).unwrap();
+        assert_eq!(key, "_id");
+        assert!(ascending);
+    }
+
+    #[test]
+    fn sorting_key_reads_key_and_direction() {
+        let sorting = UserSorting::Name(SortingDirection::Asc);
+        let (key, ascending) = sorting_key(Some(sorting)).unwrap();
+        assert_eq!(key, "name");
+        assert!(ascending);
+
+        let sorting = UserSorting::Name(SortingDirection::Desc);
+        let (key, ascending) = sorting_key(Some(sorting)).unwrap();
+        assert_eq!(key, "name");
+        assert!(!ascending);
+    }
+}
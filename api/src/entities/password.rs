@@ -0,0 +1,89 @@
+use super::*;
+
+use argon2::password_hash::Error as PasswordHashError;
+use argon2::password_hash::PasswordHash as ParsedPasswordHash;
+use argon2::password_hash::{PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+
+/// A `Password` is a plaintext password that meets our length requirements.
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct Password(String);
+
+impl Debug for Password {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Password(..)")
+    }
+}
+
+impl Password {
+    pub fn new(s: String) -> Result<Self> {
+        let len = s.chars().count();
+        ensure!(len >= 8, "too short");
+        ensure!(len <= 256, "too long");
+        let password = Self(s);
+        Ok(password)
+    }
+
+    delegate! {
+        to self.0 {
+            pub fn as_str(&self) -> &str;
+        }
+    }
+}
+
+impl TryFrom<String> for Password {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+/// A `PasswordHash` is an Argon2 hash of a password, encoded as a PHC string.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PasswordHash(String);
+
+impl Debug for PasswordHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PasswordHash(..)")
+    }
+}
+
+impl PasswordHash {
+    pub fn new(password: &Password) -> Result<Self> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(password.as_str().as_bytes(), &salt)
+            .map_err(Error::msg)
+            .context("failed to hash password")?;
+        let hash = Self(hash.to_string());
+        Ok(hash)
+    }
+
+    /// A hash of an unguessable password, used to verify against when no
+    /// real hash is available, so that failures take as long as successes.
+    pub fn placeholder() -> &'static Self {
+        lazy_static! {
+            static ref PLACEHOLDER: PasswordHash = {
+                let password = Password::new("x".repeat(32)).unwrap();
+                PasswordHash::new(&password).unwrap()
+            };
+        }
+        &PLACEHOLDER
+    }
+
+    /// Verifies `password` against this hash in constant time.
+    pub fn verify(&self, password: &str) -> Result<bool> {
+        let hash = ParsedPasswordHash::new(&self.0)
+            .map_err(Error::msg)
+            .context("failed to parse password hash")?;
+        match Argon2::default().verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(PasswordHashError::Password) => Ok(false),
+            Err(error) => {
+                Err(Error::msg(error)).context("failed to verify password")
+            }
+        }
+    }
+}
@@ -1,9 +1,14 @@
 use super::*;
 
-use phones::country::CA;
+use phones::country::Id as CountryId;
+use phones::metadata::DATABASE as PHONE_DATABASE;
 use phones::parse as parse_phone;
+use phones::Mode as PhoneMode;
+use phones::PhoneNumber;
+use phones::Type as PhoneNumberType;
 
-/// A `Phone` is a structrually valid phone number.
+/// A `Phone` is a structrually valid phone number, stored in E.164 format
+/// (i.e. `+14165550123`).
 #[derive(
     Debug, Display, Clone, Into, Hash, PartialEq, Eq, Serialize, AsRef,
 )]
@@ -21,6 +26,63 @@ impl<'de> Deserialize<'de> for Phone {
 }
 
 impl Phone {
+    /// Parses a `Phone`, assuming that it belongs to `region` unless it is
+    /// written in international format (i.e. starting with a `+`).
+    pub fn parse(s: &str, region: &PhoneRegion) -> Result<Self> {
+        let phone = parse_phone(Some(region.id()), s).with_context(|| {
+            format!("invalid phone number (assuming region {})", region)
+        })?;
+        ensure!(
+            phone.is_valid(),
+            "invalid phone number (assuming region {})",
+            region
+        );
+        let phone = Self::from_number(&phone);
+        Ok(phone)
+    }
+
+    fn from_number(number: &PhoneNumber) -> Self {
+        let e164 = number.format().mode(PhoneMode::E164);
+        Self(e164.to_string())
+    }
+
+    fn number(&self) -> PhoneNumber {
+        parse_phone(None, &self.0).expect("phone should be valid")
+    }
+
+    /// The country calling code, i.e. `1` for `+14165550123`.
+    pub fn country_code(&self) -> u16 {
+        self.number().code().value()
+    }
+
+    /// The region that this phone belongs to, if it can be determined.
+    pub fn region(&self) -> Option<PhoneRegion> {
+        // Non-geographic numbers have metadata IDs like `001`, which aren't
+        // regions
+        let metadata = self.number().metadata(&PHONE_DATABASE)?;
+        metadata.id().parse().ok()
+    }
+
+    pub fn number_type(&self) -> PhoneType {
+        let number_type = self.number().number_type(&PHONE_DATABASE);
+        number_type.into()
+    }
+
+    /// Formats this phone as it would be dialed from within its region,
+    /// i.e. `(416) 555-0123`.
+    pub fn national(&self) -> String {
+        let national = self.number().format().mode(PhoneMode::National);
+        national.to_string()
+    }
+
+    /// Formats this phone as it would be dialed from abroad, i.e.
+    /// `+1 416-555-0123`.
+    pub fn international(&self) -> String {
+        let number = self.number();
+        let international = number.format().mode(PhoneMode::International);
+        international.to_string()
+    }
+
     delegate! {
         to self.0 {
             pub fn as_str(&self) -> &str;
@@ -28,6 +90,28 @@ impl Phone {
     }
 }
 
+impl Mask for Phone {
+    /// Obscures all but the last four digits, i.e. `+*******1234`.
+    fn masked(&self) -> String {
+        let digits = self.0.chars().filter(char::is_ascii_digit).count();
+        let mut seen = 0;
+        self.0
+            .chars()
+            .map(|c| {
+                if !c.is_ascii_digit() {
+                    return c;
+                }
+                seen += 1;
+                if seen + 4 > digits {
+                    c
+                } else {
+                    '*'
+                }
+            })
+            .collect()
+    }
+}
+
 impl AsRef<str> for Phone {
     delegate! {
         to self.0 {
@@ -44,14 +128,16 @@ impl TryFrom<String> for Phone {
     }
 }
 
+/// Parses a `Phone` written in international format, since no region is
+/// assumed. Use `Phone::parse` to parse numbers in national format.
 impl FromStr for Phone {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let phone = parse_phone(CA.into(), s)?;
+        let phone = parse_phone(None, s)
+            .context("invalid phone number (expected a country code)")?;
         ensure!(phone.is_valid(), "invalid phone number");
-        let phone = phone.format();
-        let phone = Self(phone.to_string());
+        let phone = Self::from_number(&phone);
         Ok(phone)
     }
 }
@@ -62,3 +148,85 @@ impl From<Phone> for Bson {
         s.into()
     }
 }
+
+/// A `PhoneType` describes what kind of line a `Phone` reaches.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhoneType {
+    Mobile,
+    FixedLine,
+    FixedLineOrMobile,
+    TollFree,
+    Voip,
+    Other,
+}
+
+impl From<PhoneNumberType> for PhoneType {
+    fn from(number_type: PhoneNumberType) -> Self {
+        use PhoneNumberType::*;
+        match number_type {
+            Mobile => PhoneType::Mobile,
+            FixedLine => PhoneType::FixedLine,
+            FixedLineOrMobile => PhoneType::FixedLineOrMobile,
+            TollFree => PhoneType::TollFree,
+            Voip => PhoneType::Voip,
+            _ => PhoneType::Other,
+        }
+    }
+}
+
+/// A `PhoneRegion` is the two-letter (ISO 3166-1 alpha-2) code of the region
+/// that a `Phone` belongs to, like `CA` or `US`.
+#[derive(Debug, Display, Clone, Hash, PartialEq, Eq, Serialize, AsRef)]
+pub struct PhoneRegion(String);
+
+impl<'de> Deserialize<'de> for PhoneRegion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let region = String::deserialize(deserializer)?;
+        let region =
+            PhoneRegion::from_str(&region).map_err(D::Error::custom)?;
+        Ok(region)
+    }
+}
+
+impl PhoneRegion {
+    delegate! {
+        to self.0 {
+            pub fn as_str(&self) -> &str;
+        }
+    }
+
+    fn id(&self) -> CountryId {
+        self.0.parse().expect("phone region should be valid")
+    }
+}
+
+impl FromStr for PhoneRegion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let region = s.trim().to_uppercase();
+        if region.parse::<CountryId>().is_err() {
+            bail!("unknown region {}", region);
+        }
+        let region = Self(region);
+        Ok(region)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_all_but_last_four_digits() {
+        let phone = Phone("+14165550123".to_owned());
+        assert_eq!(phone.masked(), "+*******0123");
+
+        let phone = Phone("+442079460958".to_owned());
+        assert_eq!(phone.masked(), "+********0958");
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+use rand::rngs::OsRng;
+use rand::Rng;
+
+pub type PhoneVerificationId = EntityId<PhoneVerification>;
+
+/// A `PhoneVerification` is a pending attempt to prove that a `User` owns
+/// their `Phone`, by having them enter a one-time code sent to it.
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Object)]
+pub struct PhoneVerification {
+    pub user: UserId,
+    pub phone: Phone,
+    pub code: TokenHash,
+    pub expires_at: DateTime,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub attempts: u32,
+}
+
+impl Entity for PhoneVerification {
+    const NAME: &'static str = "PhoneVerification";
+
+    type Services = Services;
+    type Conditions = PhoneVerificationConditions;
+    type Sorting = EmptySorting;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+pub struct PhoneVerificationConditions {
+    #[builder(default, setter(into))]
+    pub user: Option<UserId>,
+}
+
+impl EntityConditions for PhoneVerificationConditions {
+    fn into_document(self) -> Document {
+        let PhoneVerificationConditions { user } = self;
+        let mut doc = Document::new();
+
+        if let Some(user) = user {
+            doc.insert("user", user);
+        }
+
+        doc
+    }
+}
+
+impl PhoneVerification {
+    /// The number of times a code may be guessed before it is invalidated.
+    pub const MAX_ATTEMPTS: u32 = 5;
+
+    /// How long a code remains valid after it is sent.
+    pub fn lifetime() -> Duration {
+        Duration::minutes(10)
+    }
+
+    /// How long to wait after sending a code before another can be sent.
+    pub fn resend_cooldown() -> Duration {
+        Duration::seconds(60)
+    }
+
+    /// Creates a new `PhoneVerification` for `user`'s `phone`, along with
+    /// the code to send to it.
+    ///
+    /// Codes are short enough to be brute-forced from a plain digest, so
+    /// they are stored keyed with `key`.
+    pub fn new(user: UserId, phone: Phone, key: &str) -> (Self, String) {
+        let code = format!("{:06}", OsRng.gen_range(0..1_000_000));
+        let verification = PhoneVerification {
+            user,
+            phone,
+            code: TokenHash::keyed(key, &code),
+            expires_at: now() + Self::lifetime(),
+            attempts: 0,
+        };
+        (verification, code)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+
+    pub fn matches(&self, code: &str, key: &str) -> bool {
+        self.code == TokenHash::keyed(key, code.trim())
+    }
+
+    pub fn find_by_user(user: UserId) -> FindOneQuery<Self> {
+        let conditions =
+            PhoneVerificationConditions::builder().user(user).build();
+        PhoneVerification::find_one(conditions)
+    }
+
+    /// Counts an attempt against `user`'s pending verification, returning
+    /// it if it can still be attempted.
+    ///
+    /// The attempt is counted atomically, before the code is checked, so
+    /// that concurrent guesses can't exceed `MAX_ATTEMPTS`.
+    pub async fn attempt(
+        user: UserId,
+        ctx: &Context,
+    ) -> Result<Option<Record<Self>>> {
+        let filter = doc! {
+            "user": user,
+            "attempts": { "$lt": Self::MAX_ATTEMPTS },
+            "expires_at": { "$gt": BsonDateTime::from_chrono(now()) },
+        };
+        let update = doc! { "$inc": { "attempts": 1 } };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        collection::<Self>(ctx)
+            .find_one_and_update(filter, update, options)
+            .await
+            .context("failed to update phone verification")
+    }
+}
@@ -0,0 +1,99 @@
+use super::*;
+
+pub type SessionId = EntityId<Session>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Object)]
+pub struct Session {
+    pub user: UserId,
+    pub token: TokenHash,
+    pub expires_at: DateTime,
+}
+
+impl Entity for Session {
+    const NAME: &'static str = "Session";
+
+    type Services = Services;
+    type Conditions = SessionConditions;
+    type Sorting = EmptySorting;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+pub struct SessionConditions {
+    #[builder(default, setter(into))]
+    pub user: Option<UserId>,
+
+    #[builder(default, setter(into))]
+    pub token: Option<TokenHash>,
+}
+
+impl EntityConditions for SessionConditions {
+    fn into_document(self) -> Document {
+        let SessionConditions { user, token } = self;
+        let mut doc = Document::new();
+
+        if let Some(user) = user {
+            doc.insert("user", user);
+        }
+        if let Some(token) = token {
+            doc.insert("token", token);
+        }
+
+        doc
+    }
+}
+
+impl Session {
+    /// The name of the cookie that carries a session token.
+    pub const COOKIE_NAME: &'static str = "session";
+
+    /// How long a session remains valid after it is created.
+    pub fn lifetime() -> Duration {
+        Duration::days(30)
+    }
+
+    /// Creates a new `Session` for `user`, along with the `Token` that
+    /// authenticates it.
+    pub fn new(user: UserId) -> (Self, Token) {
+        let token = Token::generate();
+        let session = Session {
+            user,
+            token: token.hash(),
+            expires_at: now() + Self::lifetime(),
+        };
+        (session, token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+
+    pub fn find_by_token(token: &Token) -> FindOneQuery<Self> {
+        let conditions =
+            SessionConditions::builder().token(token.hash()).build();
+        Session::find_one(conditions)
+    }
+
+    pub fn find_by_user(user: UserId) -> FindQuery<Self> {
+        let conditions = SessionConditions::builder().user(user).build();
+        Session::find(conditions)
+    }
+
+    /// Deletes every session belonging to `user`, logging them out
+    /// everywhere.
+    pub async fn revoke_all(user: UserId, ctx: &Context) -> Result<()> {
+        let sessions: Vec<Record<Session>> = Session::find_by_user(user)
+            .load(ctx)
+            .await
+            .context("failed to find sessions")?
+            .try_collect()
+            .await
+            .context("failed to load sessions")?;
+        for session in sessions {
+            session
+                .delete(ctx)
+                .await
+                .context("failed to delete session")?;
+        }
+        Ok(())
+    }
+}
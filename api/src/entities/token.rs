@@ -0,0 +1,91 @@
+use super::*;
+
+use hmac::{Hmac, Mac, NewMac};
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// A `Token` is a random, URL-safe secret that is handed out to clients.
+///
+/// Tokens are never persisted; store their `TokenHash` instead.
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Token(..)")
+    }
+}
+
+impl Token {
+    const LEN: usize = 32;
+
+    pub fn generate() -> Self {
+        let s: String = OsRng
+            .sample_iter(&Alphanumeric)
+            .take(Self::LEN)
+            .map(char::from)
+            .collect();
+        Self(s)
+    }
+
+    pub fn hash(&self) -> TokenHash {
+        TokenHash::digest(self.as_str())
+    }
+
+    delegate! {
+        to self.0 {
+            pub fn as_str(&self) -> &str;
+        }
+    }
+}
+
+impl FromStr for Token {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ensure!(s.len() == Self::LEN, "bad length");
+        ensure!(
+            s.chars().all(|c| c.is_ascii_alphanumeric()),
+            "invalid characters"
+        );
+        let token = Self(s.to_owned());
+        Ok(token)
+    }
+}
+
+/// A `TokenHash` is the SHA-256 digest of a `Token`, which is safe to store.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenHash(String);
+
+impl TokenHash {
+    /// Computes the SHA-256 digest of an arbitrary secret.
+    pub fn digest(secret: &str) -> Self {
+        let digest = Sha256::digest(secret.as_bytes());
+        TokenHash(format!("{:x}", digest))
+    }
+
+    /// Computes the HMAC-SHA256 of a secret that is too short to be safely
+    /// digested on its own (like a one-time code), keyed with `key`.
+    pub fn keyed(key: &str, secret: &str) -> Self {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+            .expect("HMAC should accept keys of any length");
+        mac.update(secret.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        TokenHash(format!("{:x}", digest))
+    }
+}
+
+impl From<&Token> for TokenHash {
+    fn from(token: &Token) -> Self {
+        token.hash()
+    }
+}
+
+impl From<TokenHash> for Bson {
+    fn from(hash: TokenHash) -> Self {
+        let TokenHash(s) = hash;
+        s.into()
+    }
+}
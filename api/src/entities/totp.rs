@@ -0,0 +1,241 @@
+use super::*;
+
+use base32::Alphabet as Base32Alphabet;
+use hmac::{Hmac, Mac, NewMac};
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use sha1::Sha1;
+
+const BASE32_ALPHABET: Base32Alphabet =
+    Base32Alphabet::RFC4648 { padding: false };
+
+/// A `TotpSecret` is a base32-encoded key shared with an authenticator app,
+/// for generating time-based one-time passwords (RFC 6238).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TotpSecret(String);
+
+impl Debug for TotpSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TotpSecret(..)")
+    }
+}
+
+impl TotpSecret {
+    const LEN: usize = 20;
+    const DIGITS: u32 = 6;
+    const PERIOD: i64 = 30;
+
+    pub fn generate() -> Self {
+        let mut key = [0u8; Self::LEN];
+        OsRng.fill_bytes(&mut key);
+        let secret = base32::encode(BASE32_ALPHABET, &key);
+        Self(secret)
+    }
+
+    delegate! {
+        to self.0 {
+            pub fn as_str(&self) -> &str;
+        }
+    }
+
+    /// Builds an `otpauth://` URI that authenticator apps can import (usually
+    /// by scanning it as a QR code).
+    pub fn uri(&self, issuer: &str, account: &str) -> Url {
+        let mut url = Url::parse("otpauth://totp/").unwrap();
+        url.set_path(&format!("/{}:{}", issuer, account));
+        url.query_pairs_mut()
+            .append_pair("secret", self.as_str())
+            .append_pair("issuer", issuer)
+            .append_pair("algorithm", "SHA1")
+            .append_pair("digits", &Self::DIGITS.to_string())
+            .append_pair("period", &Self::PERIOD.to_string());
+        url
+    }
+
+    /// The time step that `time` falls within.
+    fn step(time: DateTime) -> i64 {
+        time.timestamp() / Self::PERIOD
+    }
+
+    /// Generates the code for a particular time step (RFC 4226).
+    fn code(&self, step: i64) -> Result<String> {
+        let key = base32::decode(BASE32_ALPHABET, self.as_str())
+            .context("invalid secret")?;
+        let mut mac = Hmac::<Sha1>::new_from_slice(&key)
+            .map_err(Error::msg)
+            .context("failed to initialize HMAC")?;
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        let code = value % 10u32.pow(Self::DIGITS);
+        let code = format!("{:0width$}", code, width = Self::DIGITS as usize);
+        Ok(code)
+    }
+
+    /// Finds the time step, within one step of `time`, whose code is
+    /// `code`.
+    fn matching_step(&self, code: &str, time: DateTime) -> Result<Option<i64>> {
+        let current = Self::step(time);
+        for step in (current - 1)..=(current + 1) {
+            if self.code(step)? == code {
+                return Ok(Some(step));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A `Totp` is a `User`'s enrollment in TOTP two-factor authentication.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Totp {
+    pub secret: TotpSecret,
+
+    /// When the enrollment was confirmed; until then, it isn't enforced.
+    #[serde(default)]
+    pub enabled_at: Option<DateTime>,
+
+    #[serde(default)]
+    pub recovery_codes: Vec<TokenHash>,
+
+    /// The last time step whose code was accepted, which may not be used
+    /// again.
+    #[serde(default)]
+    pub last_step: Option<i64>,
+}
+
+impl Totp {
+    const RECOVERY_CODES: usize = 10;
+    const RECOVERY_CODE_LEN: usize = 10;
+
+    /// Starts a new, unconfirmed enrollment.
+    pub fn new() -> Self {
+        Totp {
+            secret: TotpSecret::generate(),
+            enabled_at: None,
+            recovery_codes: default(),
+            last_step: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+
+    /// Verifies a code from an authenticator app, rejecting codes that have
+    /// already been used.
+    pub fn verify(&mut self, code: &str) -> Result<bool> {
+        let step = match self.secret.matching_step(code.trim(), now())? {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+        if let Some(last_step) = self.last_step {
+            if step <= last_step {
+                return Ok(false);
+            }
+        }
+        self.last_step = Some(step);
+        Ok(true)
+    }
+
+    /// Replaces the recovery codes, returning the new codes in plaintext.
+    pub fn generate_recovery_codes(&mut self) -> Vec<String> {
+        let codes: Vec<String> = (0..Self::RECOVERY_CODES)
+            .map(|_| {
+                OsRng
+                    .sample_iter(&Alphanumeric)
+                    .take(Self::RECOVERY_CODE_LEN)
+                    .map(|c| char::from(c).to_ascii_lowercase())
+                    .collect()
+            })
+            .collect();
+        self.recovery_codes =
+            codes.iter().map(|code| TokenHash::digest(code)).collect();
+        codes
+    }
+
+    /// Consumes a recovery code, if it is valid.
+    pub fn redeem_recovery_code(&mut self, code: &str) -> bool {
+        let hash = TokenHash::digest(&code.trim().to_lowercase());
+        let len = self.recovery_codes.len();
+        self.recovery_codes
+            .retain(|recovery_code| recovery_code != &hash);
+        self.recovery_codes.len() < len
+    }
+
+    /// Verifies either a code from an authenticator app or a recovery code.
+    pub fn authenticate(&mut self, code: &str) -> Result<bool> {
+        if self.verify(code)? {
+            return Ok(true);
+        }
+        Ok(self.redeem_recovery_code(code))
+    }
+}
+
+impl Default for Totp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 secret from RFC 6238, Appendix B.
+    fn rfc_secret() -> TotpSecret {
+        let secret = base32::encode(BASE32_ALPHABET, b"12345678901234567890");
+        TotpSecret(secret)
+    }
+
+    #[test]
+    fn code_matches_rfc_6238_vectors() {
+        // RFC 6238 lists 8-digit codes; these are their last 6 digits
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+        let secret = rfc_secret();
+        for (time, code) in vectors {
+            let step = TotpSecret::step(Utc.timestamp(time, 0));
+            assert_eq!(secret.code(step).unwrap(), code, "at T={}", time);
+        }
+    }
+
+    #[test]
+    fn matching_step_allows_one_step_of_drift() {
+        let secret = rfc_secret();
+        let step =
+            |time| secret.matching_step("287082", Utc.timestamp(time, 0));
+        assert_eq!(step(59).unwrap(), Some(1));
+        assert_eq!(step(30).unwrap(), Some(1));
+        assert_eq!(step(0).unwrap(), Some(1));
+        assert_eq!(step(89).unwrap(), Some(1));
+        assert_eq!(step(90).unwrap(), None);
+        assert_eq!(
+            secret
+                .matching_step("000000", Utc.timestamp(59, 0))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn verify_rejects_reused_code() {
+        let mut totp = Totp::new();
+        let step = TotpSecret::step(now());
+        let code = totp.secret.code(step).unwrap();
+        assert!(totp.verify(&code).unwrap());
+        assert!(!totp.verify(&code).unwrap());
+    }
+}
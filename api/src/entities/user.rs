@@ -8,6 +8,82 @@ pub struct User {
     pub name: String,
     pub email: Email,
     pub phone: Phone,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub role: Role,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub password: Option<PasswordHash>,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub totp: Option<Totp>,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub email_verified_at: Option<DateTime>,
+
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub phone_verified_at: Option<DateTime>,
+
+    /// When the user deleted their account. Deleted users are hidden from
+    /// lookups, and purged once a grace period has passed.
+    #[serde(default)]
+    #[builder(default, setter(into))]
+    pub deleted_at: Option<DateTime>,
+}
+
+/// Published when a new `User` signs up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserSignedUp {
+    pub user: UserId,
+}
+
+impl Event for UserSignedUp {
+    const TOPIC: &'static str = "user_signed_up";
+}
+
+/// A `Role` determines what a `User` is authorized to do.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Member,
+    Admin,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Role::Member
+    }
+}
+
+impl Role {
+    /// Whether this role grants everything that `other` does.
+    pub fn includes(self, other: Role) -> bool {
+        use Role::*;
+        match (self, other) {
+            (Admin, _) => true,
+            (Member, Member) => true,
+            (Member, Admin) => false,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        use Role::*;
+        match self {
+            Member => "member",
+            Admin => "admin",
+        }
+    }
+}
+
+impl From<Role> for Bson {
+    fn from(role: Role) -> Self {
+        role.as_str().into()
+    }
 }
 
 impl Entity for User {
@@ -18,26 +94,81 @@ impl Entity for User {
     type Sorting = UserSorting;
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
 pub struct UserConditions {
+    #[builder(default, setter(into))]
+    pub id: Option<UserId>,
+
+    #[builder(default, setter(into))]
+    pub handle: Option<Handle>,
+
     #[builder(default, setter(into))]
     pub email: Option<Email>,
 
     #[builder(default, setter(into))]
     pub phone: Option<Phone>,
+
+    #[builder(default, setter(into))]
+    pub role: Option<Role>,
+
+    /// Whether to match only deleted (`true`) or only non-deleted (`false`)
+    /// users, or both (`None`). Defaults to non-deleted users.
+    #[builder(default = Some(false), setter(into))]
+    pub deleted: Option<bool>,
+
+    /// Match only users that were deleted at or before this time.
+    #[builder(default, setter(into))]
+    pub deleted_before: Option<DateTime>,
+}
+
+impl Default for UserConditions {
+    fn default() -> Self {
+        UserConditions::builder().build()
+    }
 }
 
 impl EntityConditions for UserConditions {
     fn into_document(self) -> Document {
-        let UserConditions { email, phone } = self;
+        let UserConditions {
+            id,
+            handle,
+            email,
+            phone,
+            role,
+            deleted,
+            deleted_before,
+        } = self;
         let mut doc = Document::new();
 
+        if let Some(id) = id {
+            doc.insert("_id", id);
+        }
+        if let Some(handle) = handle {
+            doc.insert("handle", handle);
+        }
         if let Some(email) = email {
             doc.insert("email", email);
         }
         if let Some(phone) = phone {
             doc.insert("phone", phone);
         }
+        if let Some(role) = role {
+            doc.insert("role", role);
+        }
+        match deleted {
+            Some(true) => {
+                doc.insert("deleted_at", doc! { "$ne": Bson::Null });
+            }
+            Some(false) => {
+                doc.insert("deleted_at", Bson::Null);
+            }
+            None => (),
+        }
+        if let Some(deleted_before) = deleted_before {
+            // Only deleted users have a `deleted_at` to compare against
+            let deleted_before = BsonDateTime::from_chrono(deleted_before);
+            doc.insert("deleted_at", doc! { "$lte": deleted_before });
+        }
 
         doc
     }
@@ -58,7 +189,78 @@ impl EntitySorting for UserSorting {
 }
 
 impl User {
+    /// Finds a user by ID, unless they have been deleted.
+    ///
+    /// This shadows `Entity::get`; use `User::get_including_deleted` to also
+    /// find deleted users.
+    pub fn get(id: UserId) -> FindOneQuery<Self> {
+        User::find_one(UserConditions::builder().id(id).build())
+    }
+
+    pub fn get_including_deleted(id: UserId) -> FindOneQuery<Self> {
+        <User as Entity>::get(id)
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn find_by_handle(handle: Handle) -> FindOneQuery<Self> {
+        User::find_one(UserConditions::builder().handle(handle).build())
+    }
+
     pub fn find_by_email(email: Email) -> FindOneQuery<Self> {
         User::find_one(UserConditions::builder().email(email).build())
     }
+
+    pub fn find_by_phone(phone: Phone) -> FindOneQuery<Self> {
+        User::find_one(UserConditions::builder().phone(phone).build())
+    }
+
+    /// Permanently deletes users (and everything that belongs to them) whose
+    /// accounts were deleted more than `grace_period` ago.
+    ///
+    /// Returns the number of users purged.
+    pub async fn purge_deleted(
+        grace_period: Duration,
+        ctx: &Context,
+    ) -> Result<usize> {
+        let cutoff = now() - grace_period;
+        let conditions = UserConditions::builder()
+            .deleted(true)
+            .deleted_before(cutoff)
+            .build();
+        let users: Vec<Record<User>> = User::find(conditions)
+            .load(ctx)
+            .await
+            .context("failed to find deleted users")?
+            .try_collect()
+            .await
+            .context("failed to load deleted users")?;
+
+        let mut purged = 0;
+        for user in users {
+            let id = user.id();
+            Session::revoke_all(id.clone(), ctx).await?;
+            ApiKey::revoke_all(id.clone(), ctx).await?;
+            FormerHandle::release_all(id.clone(), ctx).await?;
+            for kind in UserTokenKind::ALL {
+                UserToken::revoke_all(id.clone(), kind, ctx).await?;
+            }
+            let verification = PhoneVerification::find_by_user(id)
+                .optional()
+                .load(ctx)
+                .await
+                .context("failed to load phone verification")?;
+            if let Some(verification) = verification {
+                verification
+                    .delete(ctx)
+                    .await
+                    .context("failed to delete phone verification")?;
+            }
+            user.delete(ctx).await.context("failed to delete user")?;
+            purged += 1;
+        }
+        Ok(purged)
+    }
 }
@@ -0,0 +1,201 @@
+use super::*;
+
+pub type UserTokenId = EntityId<UserToken>;
+
+/// A `UserToken` is a single-use, expiring token that lets a `User` perform
+/// a particular action, like logging in from a link sent to their email.
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Object)]
+pub struct UserToken {
+    pub user: UserId,
+    pub kind: UserTokenKind,
+    pub token: TokenHash,
+    pub expires_at: DateTime,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserTokenKind {
+    LoginLink,
+    EmailVerification,
+    PasswordReset,
+    TwoFactorChallenge,
+    DataExport,
+}
+
+impl UserTokenKind {
+    pub const ALL: [UserTokenKind; 5] = [
+        UserTokenKind::LoginLink,
+        UserTokenKind::EmailVerification,
+        UserTokenKind::PasswordReset,
+        UserTokenKind::TwoFactorChallenge,
+        UserTokenKind::DataExport,
+    ];
+
+    /// How long a token of this kind remains valid after it is created.
+    pub fn lifetime(self) -> Duration {
+        use UserTokenKind::*;
+        match self {
+            LoginLink => Duration::minutes(15),
+            EmailVerification => Duration::days(1),
+            PasswordReset => Duration::hours(1),
+            TwoFactorChallenge => Duration::minutes(5),
+            DataExport => Duration::hours(1),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        use UserTokenKind::*;
+        match self {
+            LoginLink => "login_link",
+            EmailVerification => "email_verification",
+            PasswordReset => "password_reset",
+            TwoFactorChallenge => "two_factor_challenge",
+            DataExport => "data_export",
+        }
+    }
+}
+
+impl From<UserTokenKind> for Bson {
+    fn from(kind: UserTokenKind) -> Self {
+        kind.as_str().into()
+    }
+}
+
+impl Entity for UserToken {
+    const NAME: &'static str = "UserToken";
+
+    type Services = Services;
+    type Conditions = UserTokenConditions;
+    type Sorting = EmptySorting;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
+pub struct UserTokenConditions {
+    #[builder(default, setter(into))]
+    pub user: Option<UserId>,
+
+    #[builder(default, setter(into))]
+    pub kind: Option<UserTokenKind>,
+
+    #[builder(default, setter(into))]
+    pub token: Option<TokenHash>,
+}
+
+impl EntityConditions for UserTokenConditions {
+    fn into_document(self) -> Document {
+        let UserTokenConditions { user, kind, token } = self;
+        let mut doc = Document::new();
+
+        if let Some(user) = user {
+            doc.insert("user", user);
+        }
+        if let Some(kind) = kind {
+            doc.insert("kind", kind);
+        }
+        if let Some(token) = token {
+            doc.insert("token", token);
+        }
+
+        doc
+    }
+}
+
+impl UserToken {
+    /// Creates a new `UserToken` of `kind` for `user`, along with the
+    /// `Token` that redeems it.
+    pub fn new(user: UserId, kind: UserTokenKind) -> (Self, Token) {
+        let token = Token::generate();
+        let user_token = UserToken {
+            user,
+            kind,
+            token: token.hash(),
+            expires_at: now() + kind.lifetime(),
+        };
+        (user_token, token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+
+    pub fn find_by_token(
+        token: &Token,
+        kind: UserTokenKind,
+    ) -> FindOneQuery<Self> {
+        let conditions = UserTokenConditions::builder()
+            .kind(kind)
+            .token(token.hash())
+            .build();
+        UserToken::find_one(conditions)
+    }
+
+    /// Consumes the unexpired token of `kind` matching `token`, so that it
+    /// cannot be redeemed again.
+    pub async fn redeem(
+        token: &Token,
+        kind: UserTokenKind,
+        ctx: &Context,
+    ) -> Result<Option<Record<Self>>> {
+        let conditions = UserTokenConditions::builder()
+            .kind(kind)
+            .token(token.hash())
+            .build();
+        UserToken::redeem_matching(conditions, ctx).await
+    }
+
+    /// Like `redeem`, but only consumes the token if it belongs to `user`.
+    pub async fn redeem_for(
+        token: &Token,
+        kind: UserTokenKind,
+        user: UserId,
+        ctx: &Context,
+    ) -> Result<Option<Record<Self>>> {
+        let conditions = UserTokenConditions::builder()
+            .user(user)
+            .kind(kind)
+            .token(token.hash())
+            .build();
+        UserToken::redeem_matching(conditions, ctx).await
+    }
+
+    /// Finds and deletes the token matching `conditions` in one atomic
+    /// operation, so that concurrent redemptions can't both succeed.
+    async fn redeem_matching(
+        conditions: UserTokenConditions,
+        ctx: &Context,
+    ) -> Result<Option<Record<Self>>> {
+        let filter = conditions.into_document();
+        let user_token = collection::<Self>(ctx)
+            .find_one_and_delete(filter, None)
+            .await
+            .context("failed to delete token")?;
+        let user_token =
+            user_token.filter(|user_token| !user_token.is_expired());
+        Ok(user_token)
+    }
+
+    /// Deletes every token of `kind` belonging to `user`, so that none of
+    /// them can be redeemed.
+    pub async fn revoke_all(
+        user: UserId,
+        kind: UserTokenKind,
+        ctx: &Context,
+    ) -> Result<()> {
+        let conditions =
+            UserTokenConditions::builder().user(user).kind(kind).build();
+        let user_tokens: Vec<Record<UserToken>> = UserToken::find(conditions)
+            .load(ctx)
+            .await
+            .context("failed to find tokens")?
+            .try_collect()
+            .await
+            .context("failed to load tokens")?;
+        for user_token in user_tokens {
+            user_token
+                .delete(ctx)
+                .await
+                .context("failed to delete token")?;
+        }
+        Ok(())
+    }
+}
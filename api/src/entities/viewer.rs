@@ -0,0 +1,139 @@
+use super::*;
+
+/// A `Viewer` is the authenticated user on whose behalf a request is made.
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    pub user: Record<User>,
+    pub credential: Credential,
+}
+
+/// A `Credential` is what a `Viewer` authenticated with.
+#[derive(Debug, Clone)]
+pub enum Credential {
+    Session(Record<Session>),
+    ApiKey(Record<ApiKey>),
+}
+
+impl Viewer {
+    /// The session the viewer authenticated with, unless they used an API
+    /// key.
+    pub fn session(&self) -> Option<&Record<Session>> {
+        match &self.credential {
+            Credential::Session(session) => Some(session),
+            Credential::ApiKey(_) => None,
+        }
+    }
+
+    /// Resolves the `Viewer` for a session token, if it belongs to a live
+    /// session.
+    pub async fn from_session_token(
+        token: &Token,
+        ctx: &Context,
+    ) -> Result<Option<Self>> {
+        let session = Session::find_by_token(token)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load session")?;
+        let session = match session {
+            Some(session) if !session.is_expired() => session,
+            _ => return Ok(None),
+        };
+        let user = User::get(session.user.clone())
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load user")?;
+        let viewer = user.map(|user| Viewer {
+            user,
+            credential: Credential::Session(session),
+        });
+        Ok(viewer)
+    }
+
+    /// Resolves the `Viewer` for an API key, if it is valid, and records
+    /// that the key was used.
+    pub async fn from_api_key(
+        token: &Token,
+        ctx: &Context,
+    ) -> Result<Option<Self>> {
+        let api_key = ApiKey::find_by_key(token)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load API key")?;
+        let mut api_key = match api_key {
+            Some(api_key) if !api_key.is_expired() => api_key,
+            _ => return Ok(None),
+        };
+        let user = User::get(api_key.user.clone())
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load user")?;
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        // Record usage, at most once a minute to avoid a write per request
+        let now = now();
+        let stale = match api_key.last_used_at {
+            Some(last_used_at) => now - last_used_at >= Duration::minutes(1),
+            None => true,
+        };
+        if stale {
+            api_key.last_used_at = Some(now);
+            api_key.save(ctx).await.context("failed to save API key")?;
+        }
+
+        let viewer = Viewer {
+            user,
+            credential: Credential::ApiKey(api_key),
+        };
+        Ok(Some(viewer))
+    }
+
+    /// Reloads the viewer's credential and user, returning `None` if the
+    /// credential has since been revoked or expired, or the user deleted.
+    ///
+    /// Long-lived connections resolve the viewer once, so they should call
+    /// this to pick up changes (like a role being revoked) since then.
+    pub async fn reload(&self, ctx: &Context) -> Result<Option<Self>> {
+        let credential = match &self.credential {
+            Credential::Session(session) => {
+                let session = Session::get(session.id())
+                    .optional()
+                    .load(ctx)
+                    .await
+                    .context("failed to load session")?;
+                match session {
+                    Some(session) if !session.is_expired() => {
+                        Credential::Session(session)
+                    }
+                    _ => return Ok(None),
+                }
+            }
+            Credential::ApiKey(api_key) => {
+                let api_key = ApiKey::get(api_key.id())
+                    .optional()
+                    .load(ctx)
+                    .await
+                    .context("failed to load API key")?;
+                match api_key {
+                    Some(api_key) if !api_key.is_expired() => {
+                        Credential::ApiKey(api_key)
+                    }
+                    _ => return Ok(None),
+                }
+            }
+        };
+        let user = User::get(self.user.id())
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to load user")?;
+        let viewer = user.map(|user| Viewer { user, credential });
+        Ok(viewer)
+    }
+}
@@ -1,29 +1,51 @@
+mod loader;
 mod mutation;
 mod query;
 mod subscription;
 
+pub use loader::*;
 pub use mutation::*;
 pub use query::*;
 pub use subscription::*;
 
+mod api_key;
+mod auth;
 mod build;
+mod connection;
+mod data_export;
 mod date_time;
 mod id;
+mod node;
+mod phone;
+mod role;
 mod test;
+mod two_factor;
 mod user;
+mod validation;
+mod verification;
 // mod date;
 
+use api_key::*;
+use auth::*;
 use build::*;
+use connection::*;
+use data_export::*;
 use date_time::*;
 use id::*;
+use node::*;
+use phone::*;
+use role::*;
 use test::*;
+use two_factor::*;
 use user::*;
+use validation::*;
+use verification::*;
 // use date::*
 
 use super::*;
 
 use entities::{Context as EntityContext, *};
-use services::Services;
+use services::{Mail, Services, Settings, Sms};
 
 use entrust::{Comparison, Record, SortingDirection};
 use entrust::{Entity, EntityId};
@@ -33,7 +55,7 @@ use graphql::Context;
 use graphql::SimpleObject;
 use graphql::Value;
 use graphql::{Enum, EnumType};
-use graphql::{FieldError, FieldResult};
+use graphql::{ErrorExtensions, FieldError, FieldResult};
 use graphql::{InputObject, InputObjectType};
 use graphql::{InputValueError, InputValueResult};
 use graphql::{Interface, InterfaceType};
@@ -44,6 +66,9 @@ use graphql::{Union, UnionType};
 
 trait ContextExt {
     fn services(&self) -> Services;
+    fn viewer(&self) -> Option<&Viewer>;
+    fn require_viewer(&self) -> FieldResult<&Viewer>;
+    fn require_session(&self) -> FieldResult<&Viewer>;
 
     // async fn transact<F, T, U>(&self, f: F) -> FieldResult<T>
     // where
@@ -64,6 +89,45 @@ impl<'a> ContextExt for Context<'a> {
         let services = self.data_unchecked::<Services>();
         services.to_owned()
     }
+
+    fn viewer(&self) -> Option<&Viewer> {
+        self.data_opt::<Viewer>()
+    }
+
+    fn require_viewer(&self) -> FieldResult<&Viewer> {
+        self.viewer().ok_or_else(unauthenticated)
+    }
+
+    /// Requires a viewer that authenticated with a session, for fields that
+    /// must not be reachable with just an API key (like those that change
+    /// how the account is secured).
+    fn require_session(&self) -> FieldResult<&Viewer> {
+        let viewer = self.require_viewer()?;
+        match viewer.session() {
+            Some(_) => Ok(viewer),
+            None => Err(session_required()),
+        }
+    }
+}
+
+/// The error returned when a field requires an authenticated viewer, but
+/// there isn't one.
+fn unauthenticated() -> FieldError {
+    FieldError::new("not authenticated")
+        .extend_with(|_, extensions| extensions.set("code", "UNAUTHENTICATED"))
+}
+
+/// The error returned when a field requires a viewer that authenticated with
+/// a session, but they used an API key.
+fn session_required() -> FieldError {
+    FieldError::new("must be authenticated with a session")
+        .extend_with(|_, extensions| extensions.set("code", "SESSION_REQUIRED"))
+}
+
+/// The error returned when the viewer isn't authorized to access a field.
+fn forbidden() -> FieldError {
+    FieldError::new("forbidden")
+        .extend_with(|_, extensions| extensions.set("code", "FORBIDDEN"))
 }
 
 pub(super) trait ResultExt<T> {
@@ -0,0 +1,171 @@
+use super::*;
+
+#[derive(Debug, Clone, From)]
+pub(super) struct ApiKeyObject {
+    pub record: Record<ApiKey>,
+}
+
+#[Object(name = "ApiKey")]
+impl ApiKeyObject {
+    async fn id(&self) -> Id<ApiKey> {
+        self.record.id().into()
+    }
+
+    async fn created_at(&self) -> DateTimeScalar {
+        self.record.created_at().into()
+    }
+
+    async fn name(&self) -> &str {
+        &self.record.name
+    }
+
+    async fn prefix(&self) -> &str {
+        &self.record.prefix
+    }
+
+    async fn last_used_at(&self) -> Option<DateTimeScalar> {
+        self.record.last_used_at.map(Into::into)
+    }
+
+    async fn expires_at(&self) -> Option<DateTimeScalar> {
+        self.record.expires_at.map(Into::into)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ApiKeyQuery;
+
+#[Object]
+impl ApiKeyQuery {
+    /// The viewer's API keys.
+    async fn api_keys(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<ApiKeyObject>> {
+        let viewer = ctx.require_viewer()?;
+        let user = viewer.user.id();
+
+        let services = ctx.services();
+        let ctx = EntityContext::new(services);
+
+        let api_keys: Vec<Record<ApiKey>> = ApiKey::find_by_user(user)
+            .load(&ctx)
+            .await
+            .context("failed to find API keys")
+            .into_field_result()?
+            .try_collect()
+            .await
+            .context("failed to load API keys")
+            .into_field_result()?;
+        let api_keys = api_keys.into_iter().map(ApiKeyObject::from).collect();
+        Ok(api_keys)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ApiKeyMutation;
+
+#[Object]
+impl ApiKeyMutation {
+    async fn create_api_key(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateApiKeyInput,
+    ) -> FieldResult<CreateApiKeyPayload> {
+        let CreateApiKeyInput { name, expires_at } = input;
+        let user = ctx.require_session()?.user.id();
+
+        let services = ctx.services();
+        let ctx = EntityContext::new(services);
+
+        // Validate input
+        let mut errors = ValidationErrors::new();
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            errors.push("name", "must not be empty");
+        }
+        let expires_at: Option<DateTime> = expires_at.map(Into::into);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= now() {
+                errors.push("expiresAt", "must be in the future");
+            }
+        }
+        if !errors.is_empty() {
+            let payload = CreateApiKeyPayload {
+                api_key: None,
+                key: None,
+                errors: errors.into(),
+            };
+            return Ok(payload);
+        }
+
+        // Create API key
+        let (api_key, key) = ApiKey::new(user, name, expires_at);
+        let mut api_key = Record::new(api_key);
+        api_key
+            .save(&ctx)
+            .await
+            .context("failed to save API key")
+            .into_field_result()?;
+
+        let payload = CreateApiKeyPayload {
+            api_key: Some(api_key.into()),
+            key: Some(key),
+            errors: default(),
+        };
+        Ok(payload)
+    }
+
+    async fn revoke_api_key(
+        &self,
+        ctx: &Context<'_>,
+        id: Id<ApiKey>,
+    ) -> FieldResult<RevokeApiKeyPayload> {
+        let user = ctx.require_session()?.user.id();
+
+        let services = ctx.services();
+        let ctx = EntityContext::new(services);
+
+        let api_key = ApiKey::get(id.into())
+            .optional()
+            .load(&ctx)
+            .await
+            .context("failed to load API key")
+            .into_field_result()?;
+        let api_key = match api_key {
+            Some(api_key) if api_key.user == user => api_key,
+            _ => {
+                let error = FieldError::new("API key not found");
+                return Err(error);
+            }
+        };
+        api_key
+            .delete(&ctx)
+            .await
+            .context("failed to delete API key")
+            .into_field_result()?;
+
+        Ok(RevokeApiKeyPayload { ok: true })
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct CreateApiKeyInput {
+    pub name: String,
+    pub expires_at: Option<DateTimeScalar>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct CreateApiKeyPayload {
+    pub api_key: Option<ApiKeyObject>,
+
+    /// The plaintext API key, which is only ever shown once.
+    pub key: Option<String>,
+
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct RevokeApiKeyPayload {
+    pub ok: bool,
+}
@@ -0,0 +1,458 @@
+use super::*;
+
+use http::header::SET_COOKIE;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AuthMutation;
+
+#[Object]
+impl AuthMutation {
+    async fn login(
+        &self,
+        ctx: &Context<'_>,
+        input: LoginInput,
+    ) -> FieldResult<LoginPayload> {
+        let LoginInput { email, password } = input;
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        // Find user with a password credential
+        let user = match Email::try_from(email) {
+            Ok(email) => User::find_by_email(email)
+                .optional()
+                .load(&entity_ctx)
+                .await
+                .context("failed to lookup user by email")
+                .into_field_result()?,
+            Err(_) => None,
+        };
+        let hash = user.as_ref().and_then(|user| user.password.clone());
+
+        // Verify password, falling back to a placeholder hash so that unknown
+        // emails take just as long to reject
+        let verified = spawn_blocking(move || match hash {
+            Some(hash) => hash.verify(&password),
+            None => {
+                PasswordHash::placeholder().verify(&password)?;
+                Ok(false)
+            }
+        })
+        .await
+        .context("failed to join password verification task")
+        .into_field_result()?
+        .into_field_result()?;
+
+        let user = match user {
+            Some(user) if verified => user,
+            _ => {
+                let error = FieldError::new("invalid email or password");
+                return Err(error);
+            }
+        };
+
+        complete_login(ctx, user).await
+    }
+
+    /// Sends a link to `email` that logs its user in, if such a user exists.
+    ///
+    /// Always succeeds, so as not to reveal which emails are registered.
+    async fn request_login_link(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> FieldResult<RequestLoginLinkPayload> {
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let email = match Email::try_from(email) {
+            Ok(email) => email,
+            Err(_) => return Ok(RequestLoginLinkPayload { ok: true }),
+        };
+        let user = User::find_by_email(email)
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to lookup user by email")
+            .into_field_result()?;
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(RequestLoginLinkPayload { ok: true }),
+        };
+
+        // Mint login token
+        let (user_token, token) =
+            UserToken::new(user.id(), UserTokenKind::LoginLink);
+        let mut user_token = Record::new(user_token);
+        user_token
+            .save(&entity_ctx)
+            .await
+            .context("failed to save login token")
+            .into_field_result()?;
+
+        // Send login link
+        let link = {
+            let mut url = services
+                .settings()
+                .web_public_url_for("login/link")
+                .into_field_result()?;
+            url.query_pairs_mut().append_pair("token", token.as_str());
+            url
+        };
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Your login link".to_owned(),
+            body: format!(
+                "Hi {}, use the following link to log in: {}\n\n\
+                 This link expires in {} minutes.",
+                &user.name,
+                &link,
+                UserTokenKind::LoginLink.lifetime().num_minutes(),
+            ),
+        };
+        if let Err(error) = services.mailer().send(mail).await {
+            error!(
+                target: "template_api::graph",
+                user = %user.id(),
+                error = %format!("{:#}", error),
+                "failed to send login link",
+            );
+        }
+
+        Ok(RequestLoginLinkPayload { ok: true })
+    }
+
+    async fn redeem_login_link(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> FieldResult<LoginPayload> {
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        let token: Token = token
+            .parse()
+            .map_err(|_| FieldError::new("invalid or expired login link"))?;
+        let user_token =
+            UserToken::redeem(&token, UserTokenKind::LoginLink, &entity_ctx)
+                .await
+                .into_field_result()?
+                .ok_or_else(|| {
+                    FieldError::new("invalid or expired login link")
+                })?;
+        let user = User::get(user_token.user.clone())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to load user")
+            .into_field_result()?
+            .ok_or_else(|| FieldError::new("invalid or expired login link"))?;
+
+        complete_login(ctx, user).await
+    }
+
+    /// Sends a link to `email` for resetting its user's password, if such a
+    /// user exists.
+    ///
+    /// Always succeeds, so as not to reveal which emails are registered.
+    async fn request_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> FieldResult<RequestPasswordResetPayload> {
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let email = match Email::try_from(email) {
+            Ok(email) => email,
+            Err(_) => return Ok(RequestPasswordResetPayload { ok: true }),
+        };
+        let user = User::find_by_email(email)
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to lookup user by email")
+            .into_field_result()?;
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(RequestPasswordResetPayload { ok: true }),
+        };
+
+        // Mint reset token
+        let (user_token, token) =
+            UserToken::new(user.id(), UserTokenKind::PasswordReset);
+        let mut user_token = Record::new(user_token);
+        user_token
+            .save(&entity_ctx)
+            .await
+            .context("failed to save password reset token")
+            .into_field_result()?;
+
+        // Send reset link
+        let link = {
+            let mut url = services
+                .settings()
+                .web_public_url_for("reset-password")
+                .into_field_result()?;
+            url.query_pairs_mut().append_pair("token", token.as_str());
+            url
+        };
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Reset your password".to_owned(),
+            body: format!(
+                "Hi {}, use the following link to reset your password: {}\n\n\
+                 This link expires in {} minutes. If you didn't request a \
+                 password reset, you can safely ignore this email.",
+                &user.name,
+                &link,
+                UserTokenKind::PasswordReset.lifetime().num_minutes(),
+            ),
+        };
+        if let Err(error) = services.mailer().send(mail).await {
+            error!(
+                target: "template_api::graph",
+                user = %user.id(),
+                error = %format!("{:#}", error),
+                "failed to send password reset link",
+            );
+        }
+
+        Ok(RequestPasswordResetPayload { ok: true })
+    }
+
+    /// Sets a new password using a token from `requestPasswordReset`, and
+    /// logs the user out of all existing sessions.
+    async fn reset_password(
+        &self,
+        ctx: &Context<'_>,
+        input: ResetPasswordInput,
+    ) -> FieldResult<ResetPasswordPayload> {
+        let ResetPasswordInput {
+            token,
+            new_password,
+        } = input;
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        // Validate input
+        let mut errors = ValidationErrors::new();
+        let password = errors.check("newPassword", Password::new(new_password));
+        let password = match password {
+            Some(password) => password,
+            None => {
+                let payload = ResetPasswordPayload {
+                    ok: false,
+                    errors: errors.into(),
+                };
+                return Ok(payload);
+            }
+        };
+
+        // Redeem reset token
+        let token: Token = token
+            .parse()
+            .map_err(|_| FieldError::new("invalid or expired reset link"))?;
+        let user_token = UserToken::redeem(
+            &token,
+            UserTokenKind::PasswordReset,
+            &entity_ctx,
+        )
+        .await
+        .into_field_result()?
+        .ok_or_else(|| FieldError::new("invalid or expired reset link"))?;
+        let mut user = User::get(user_token.user.clone())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to load user")
+            .into_field_result()?
+            .ok_or_else(|| FieldError::new("invalid or expired reset link"))?;
+
+        // Update password
+        let hash = spawn_blocking(move || PasswordHash::new(&password))
+            .await
+            .context("failed to join password hashing task")
+            .into_field_result()?
+            .into_field_result()?;
+        user.password = Some(hash);
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        // Revoke existing sessions, and any other links that could sign in
+        Session::revoke_all(user.id(), &entity_ctx)
+            .await
+            .into_field_result()?;
+        for kind in [UserTokenKind::PasswordReset, UserTokenKind::LoginLink] {
+            UserToken::revoke_all(user.id(), kind, &entity_ctx)
+                .await
+                .into_field_result()?;
+        }
+
+        let payload = ResetPasswordPayload {
+            ok: true,
+            errors: default(),
+        };
+        Ok(payload)
+    }
+
+    async fn logout(&self, ctx: &Context<'_>) -> FieldResult<LogoutPayload> {
+        let session = match ctx.viewer().and_then(Viewer::session) {
+            Some(session) => session.to_owned(),
+            None => return Ok(LogoutPayload { ok: false }),
+        };
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let cookie = session_cookie(services.settings(), None, &session);
+        session
+            .delete(&entity_ctx)
+            .await
+            .context("failed to delete session")
+            .into_field_result()?;
+        ctx.insert_http_header(SET_COOKIE, cookie);
+
+        Ok(LogoutPayload { ok: true })
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct LoginInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct LoginPayload {
+    pub user: Option<UserObject>,
+
+    /// A session token, for clients that authenticate with an
+    /// `Authorization: Bearer` header instead of cookies.
+    pub token: Option<String>,
+
+    /// Set when the user has enabled two-factor authentication, in which
+    /// case it must be passed to `verifyTwoFactorLogin` along with a code to
+    /// finish logging in.
+    pub two_factor_challenge: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct LogoutPayload {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct RequestPasswordResetPayload {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct ResetPasswordInput {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct ResetPasswordPayload {
+    pub ok: bool,
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct RequestLoginLinkPayload {
+    pub ok: bool,
+}
+
+/// Logs in as `user`, unless they have enabled two-factor authentication, in
+/// which case a challenge is issued instead.
+async fn complete_login(
+    ctx: &Context<'_>,
+    user: Record<User>,
+) -> FieldResult<LoginPayload> {
+    if !matches!(&user.totp, Some(totp) if totp.is_enabled()) {
+        return start_session(ctx, user).await;
+    }
+
+    let services = ctx.services();
+    let entity_ctx = EntityContext::new(services);
+
+    let (user_token, token) =
+        UserToken::new(user.id(), UserTokenKind::TwoFactorChallenge);
+    let mut user_token = Record::new(user_token);
+    user_token
+        .save(&entity_ctx)
+        .await
+        .context("failed to save two-factor challenge")
+        .into_field_result()?;
+
+    let payload = LoginPayload {
+        user: None,
+        token: None,
+        two_factor_challenge: Some(token.as_str().to_owned()),
+    };
+    Ok(payload)
+}
+
+/// Starts a new session for `user`, and sets the session cookie on the
+/// response.
+pub(super) async fn start_session(
+    ctx: &Context<'_>,
+    mut user: Record<User>,
+) -> FieldResult<LoginPayload> {
+    let services = ctx.services();
+    let entity_ctx = EntityContext::new(services.clone());
+
+    if promote_if_admin_email(services.settings(), &mut user) {
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+    }
+
+    let (session, token) = Session::new(user.id());
+    let mut session = Record::new(session);
+    session
+        .save(&entity_ctx)
+        .await
+        .context("failed to save session")
+        .into_field_result()?;
+    let cookie = session_cookie(services.settings(), Some(&token), &session);
+    ctx.insert_http_header(SET_COOKIE, cookie);
+
+    let payload = LoginPayload {
+        user: Some(UserObject::for_viewer(user)),
+        token: Some(token.as_str().to_owned()),
+        two_factor_challenge: None,
+    };
+    Ok(payload)
+}
+
+/// Builds a `Set-Cookie` header value that stores `token` as the session
+/// cookie, or clears the session cookie if `token` is `None`.
+pub(super) fn session_cookie(
+    settings: &Settings,
+    token: Option<&Token>,
+    session: &Session,
+) -> String {
+    let (value, max_age) = match token {
+        Some(token) => {
+            let max_age = (session.expires_at - now()).num_seconds().max(0);
+            (token.as_str(), max_age)
+        }
+        None => ("", 0),
+    };
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict",
+        Session::COOKIE_NAME,
+        value,
+        max_age
+    );
+    if settings.api_public_url.scheme() == "https" {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
@@ -0,0 +1,93 @@
+use super::*;
+
+use graphql::connection::{query as query_connection, CursorType};
+use graphql::connection::{Connection, Edge, EmptyFields};
+use graphql::OutputType;
+
+/// A Relay connection over entities, with opaque cursors.
+pub(super) type EntityConnection<T> =
+    Connection<Cursor, T, ConnectionFields, EmptyFields>;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct ConnectionFields {
+    /// The number of nodes across all pages.
+    pub total_count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "SortingDirection", remote = "SortingDirection")]
+pub(super) enum SortingDirectionEnum {
+    Asc,
+    Desc,
+}
+
+impl CursorType for Cursor {
+    type Error = Error;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        Cursor::decode(s).context("invalid cursor")
+    }
+
+    fn encode_cursor(&self) -> String {
+        self.encode()
+    }
+}
+
+/// Loads a page of entities matching `conditions` as a Relay connection,
+/// where `after`, `before`, `first`, and `last` are the standard connection
+/// arguments.
+pub(super) async fn paginate<E, T>(
+    ctx: &Context<'_>,
+    conditions: Option<E::Conditions>,
+    sorting: Option<E::Sorting>,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+) -> FieldResult<EntityConnection<T>>
+where
+    E: Entity<Services = Services> + Send + Sync,
+    E::Conditions: Clone + Send + Sync,
+    E::Sorting: Clone + Send + Sync,
+    T: From<Record<E>> + OutputType + Send + Sync,
+{
+    let services = ctx.services();
+    let ctx = EntityContext::new(services);
+
+    query_connection(
+        after,
+        before,
+        first,
+        last,
+        |after, before, first, last| async move {
+            let query = PageQuery::<E>::builder()
+                .conditions(conditions)
+                .sorting(sorting)
+                .after(after)
+                .before(before)
+                .first(first)
+                .last(last)
+                .build();
+            let total_count = query.count(&ctx).await.into_field_result()?;
+            let Page {
+                records,
+                has_previous,
+                has_next,
+            } = query.load(&ctx).await.into_field_result()?;
+
+            let fields = ConnectionFields { total_count };
+            let mut connection = Connection::with_additional_fields(
+                has_previous,
+                has_next,
+                fields,
+            );
+            connection.append(
+                records
+                    .into_iter()
+                    .map(|(cursor, record)| Edge::new(cursor, record.into())),
+            );
+            Ok::<_, FieldError>(connection)
+        },
+    )
+    .await
+}
@@ -0,0 +1,52 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct DataExportMutation;
+
+#[Object]
+impl DataExportMutation {
+    /// Returns a link for downloading everything stored about the viewer, as
+    /// a JSON archive.
+    ///
+    /// The link must be opened while logged in as the viewer. It can only be
+    /// used once, and expires after an hour.
+    async fn request_data_export(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<RequestDataExportPayload> {
+        let user = ctx.require_session()?.user.id();
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        // Mint export token
+        let (user_token, token) =
+            UserToken::new(user, UserTokenKind::DataExport);
+        let mut user_token = Record::new(user_token);
+        user_token
+            .save(&entity_ctx)
+            .await
+            .context("failed to save data export token")
+            .into_field_result()?;
+
+        let url = {
+            let mut url = services
+                .settings()
+                .api_public_url_for("export")
+                .into_field_result()?;
+            url.query_pairs_mut().append_pair("token", token.as_str());
+            url
+        };
+        let payload = RequestDataExportPayload {
+            url,
+            expires_at: user_token.expires_at.into(),
+        };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct RequestDataExportPayload {
+    pub url: Url,
+    pub expires_at: DateTimeScalar,
+}
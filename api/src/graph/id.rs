@@ -1,15 +1,19 @@
 use super::*;
 
+/// A global ID for an entity of type `T`, which encodes `T`'s name along
+/// with its `EntityId` (i.e. `User:6172f2d1a0b6d5d1e4c3b2a1`).
 #[derive(Debug, Clone, From, Into, Deref)]
 pub(super) struct Id<T: Entity>(EntityId<T>);
 
 #[Scalar(name = "ID")]
 impl<T: Entity> ScalarType for Id<T> {
     fn parse(value: Value) -> InputValueResult<Self> {
-        let id = match value {
-            Value::String(s) => s,
-            _ => return Err(InputValueError::expected_type(value)),
-        };
+        let GlobalId { name, id } =
+            GlobalId::parse(value).map_err(InputValueError::propagate)?;
+        if name != T::NAME {
+            let message = format!("expected a {} ID", T::NAME);
+            return Err(InputValueError::custom(message));
+        }
         let id: EntityId<T> = id.parse().map_err(|error| {
             let message = format!("{:?}", &error);
             InputValueError::custom(message)
@@ -19,6 +23,64 @@ impl<T: Entity> ScalarType for Id<T> {
 
     fn to_value(&self) -> Value {
         let Id(id) = self;
-        Value::String(id.to_string())
+        Value::String(format!("{}:{}", T::NAME, id))
+    }
+}
+
+/// A global ID for an entity of any type, for fields like `node` where the
+/// type isn't known up front.
+#[derive(Debug, Clone)]
+pub(super) struct GlobalId {
+    /// The `Entity::NAME` of the entity's type.
+    pub name: String,
+    pub id: String,
+}
+
+impl GlobalId {
+    /// Converts this into an `Id<T>`, if it belongs to an entity of type
+    /// `T`.
+    pub fn typed<T: Entity>(&self) -> Option<Id<T>> {
+        if self.name != T::NAME {
+            return None;
+        }
+        let id: EntityId<T> = self.id.parse().ok()?;
+        Some(Id(id))
+    }
+}
+
+#[Scalar(name = "ID")]
+impl ScalarType for GlobalId {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        let global_id = match value {
+            Value::String(s) => s,
+            _ => return Err(InputValueError::expected_type(value)),
+        };
+        let (name, id) = match global_id.split_once(':') {
+            Some(parts) => parts,
+            None => {
+                let message = "invalid global ID";
+                return Err(InputValueError::custom(message));
+            }
+        };
+        let global_id = GlobalId {
+            name: name.to_owned(),
+            id: id.to_owned(),
+        };
+        Ok(global_id)
+    }
+
+    fn to_value(&self) -> Value {
+        let GlobalId { name, id } = self;
+        Value::String(format!("{}:{}", name, id))
+    }
+}
+
+impl<T: Entity> From<Id<T>> for GlobalId {
+    fn from(id: Id<T>) -> Self {
+        let Id(id) = id;
+        GlobalId {
+            name: T::NAME.to_owned(),
+            id: id.to_string(),
+        }
     }
 }
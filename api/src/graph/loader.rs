@@ -0,0 +1,92 @@
+use super::*;
+
+use graphql::dataloader::{DataLoader, HashMapCache, Loader};
+
+use ::bson::{doc, Bson};
+use std::hash::Hash;
+
+/// An `EntityLoader` batches lookups of entities by ID into a single query.
+#[derive(Debug, Clone)]
+pub struct EntityLoader {
+    services: Services,
+}
+
+impl EntityLoader {
+    /// Builds a `DataLoader` that caches the entities it loads for as long as
+    /// it lives, so one should be built for each operation.
+    pub fn data_loader(services: Services) -> DataLoader<Self, HashMapCache> {
+        let loader = EntityLoader { services };
+        DataLoader::with_cache(loader, HashMapCache::default())
+    }
+
+    async fn load_records<T>(
+        &self,
+        ids: &[EntityId<T>],
+    ) -> Result<Vec<Record<T>>>
+    where
+        T: Entity<Services = Services>,
+    {
+        let ctx = EntityContext::new(self.services.clone());
+        let ids: Vec<Bson> = ids.iter().cloned().map(Into::into).collect();
+        let pipeline = vec![doc! { "$match": { "_id": { "$in": ids } } }];
+        let records: Vec<Record<T>> = T::aggregate(pipeline)
+            .load(&ctx)
+            .await
+            .context("failed to find records")?
+            .try_collect()
+            .await
+            .context("failed to load records")?;
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl<T> Loader<EntityId<T>> for EntityLoader
+where
+    T: Entity<Services = Services> + Clone + Send + Sync + 'static,
+    EntityId<T>: Hash + Eq,
+{
+    type Value = Record<T>;
+    type Error = Arc<Error>;
+
+    async fn load(
+        &self,
+        ids: &[EntityId<T>],
+    ) -> Result<Map<EntityId<T>, Record<T>>, Self::Error> {
+        let records = self.load_records(ids).await.map_err(Arc::new)?;
+        let records = records
+            .into_iter()
+            .map(|record| (record.id(), record))
+            .collect();
+        Ok(records)
+    }
+}
+
+/// Loads the entity with `id`, through the operation's `EntityLoader` if
+/// there is one, so that lookups made while resolving a single operation are
+/// batched and cached.
+pub(super) async fn load_entity<T>(
+    ctx: &Context<'_>,
+    id: EntityId<T>,
+) -> Result<Option<Record<T>>>
+where
+    T: Entity<Services = Services> + Clone + Send + Sync + 'static,
+    EntityId<T>: Hash + Eq,
+{
+    if let Some(loader) =
+        ctx.data_opt::<DataLoader<EntityLoader, HashMapCache>>()
+    {
+        return loader
+            .load_one(id)
+            .await
+            .map_err(|error| Error::msg(format!("{:#}", error)));
+    }
+
+    let services = ctx.services();
+    let ctx = EntityContext::new(services);
+    T::get(id)
+        .optional()
+        .load(&ctx)
+        .await
+        .context("failed to load entity")
+}
@@ -1,11 +1,27 @@
 use super::*;
 
 #[derive(Debug, Clone, Copy, MergedObject)]
-pub struct Mutation(TestMutation);
+pub struct Mutation(
+    TestMutation,
+    UserMutation,
+    AuthMutation,
+    VerificationMutation,
+    TwoFactorMutation,
+    ApiKeyMutation,
+    DataExportMutation,
+);
 
 impl Mutation {
     pub fn new() -> Self {
-        Self(TestMutation)
+        Self(
+            TestMutation,
+            UserMutation,
+            AuthMutation,
+            VerificationMutation,
+            TwoFactorMutation,
+            ApiKeyMutation,
+            DataExportMutation,
+        )
     }
 }
 
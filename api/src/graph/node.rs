@@ -0,0 +1,48 @@
+use super::*;
+
+/// An object that can be refetched by its global ID, per the Relay Global
+/// Object Identification spec.
+#[derive(Interface)]
+#[graphql(field(name = "id", type = "GlobalId"))]
+pub(super) enum Node {
+    User(UserObject),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct NodeQuery;
+
+#[Object]
+impl NodeQuery {
+    /// Fetches an object by its global ID.
+    async fn node(
+        &self,
+        ctx: &Context<'_>,
+        id: GlobalId,
+    ) -> FieldResult<Option<Node>> {
+        load_node(ctx, &id).await
+    }
+
+    /// Fetches objects by their global IDs, in the same order.
+    async fn nodes(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<GlobalId>,
+    ) -> FieldResult<Vec<Option<Node>>> {
+        let nodes = ids.iter().map(|id| load_node(ctx, id));
+        try_join_all(nodes).await
+    }
+}
+
+/// Loads the object identified by `id`, or `None` if it doesn't exist or
+/// isn't of a type that implements `Node`.
+async fn load_node(
+    ctx: &Context<'_>,
+    id: &GlobalId,
+) -> FieldResult<Option<Node>> {
+    if let Some(id) = id.typed::<User>() {
+        let user = load_user(ctx, id.into()).await?;
+        let node = user.map(|user| Node::User(user.into()));
+        return Ok(node);
+    }
+    Ok(None)
+}
@@ -0,0 +1,67 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "PhoneType", remote = "PhoneType")]
+pub(super) enum PhoneTypeEnum {
+    Mobile,
+    FixedLine,
+    FixedLineOrMobile,
+    TollFree,
+    Voip,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct PhoneObject {
+    pub phone: Phone,
+
+    /// Whether to obscure this phone, in which case only its masked number
+    /// is shown.
+    pub masked: bool,
+}
+
+#[Object(name = "Phone")]
+impl PhoneObject {
+    /// The phone number in E.164 format (i.e. `+14165550123`), or with all
+    /// but its last four digits obscured if it is private.
+    async fn number(&self) -> String {
+        if self.masked {
+            self.phone.masked()
+        } else {
+            self.phone.to_string()
+        }
+    }
+
+    async fn national(&self) -> Option<String> {
+        self.visible().map(Phone::national)
+    }
+
+    async fn international(&self) -> Option<String> {
+        self.visible().map(Phone::international)
+    }
+
+    async fn country_code(&self) -> Option<u16> {
+        self.visible().map(Phone::country_code)
+    }
+
+    /// The two-letter code of the region that this phone belongs to.
+    async fn region(&self) -> Option<String> {
+        let region = self.visible()?.region()?;
+        Some(region.as_str().to_owned())
+    }
+
+    #[graphql(name = "type")]
+    async fn number_type(&self) -> Option<PhoneTypeEnum> {
+        self.visible().map(|phone| phone.number_type().into())
+    }
+}
+
+impl PhoneObject {
+    fn visible(&self) -> Option<&Phone> {
+        if self.masked {
+            None
+        } else {
+            Some(&self.phone)
+        }
+    }
+}
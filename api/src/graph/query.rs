@@ -1,11 +1,11 @@
 use super::*;
 
 #[derive(Debug, Clone, Copy, MergedObject)]
-pub struct Query(BuildQuery, UserQuery);
+pub struct Query(BuildQuery, NodeQuery, UserQuery, ApiKeyQuery);
 
 impl Query {
     pub fn new() -> Self {
-        Self(BuildQuery, UserQuery)
+        Self(BuildQuery, NodeQuery, UserQuery, ApiKeyQuery)
     }
 }
 
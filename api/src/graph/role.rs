@@ -0,0 +1,70 @@
+use super::*;
+
+use graphql::guard::Guard;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "Role", remote = "Role")]
+pub(super) enum RoleEnum {
+    Member,
+    Admin,
+}
+
+/// A `RoleGuard` restricts a field to viewers whose role includes `role`.
+///
+/// ```ignore
+/// #[graphql(guard(RoleGuard(role = "Role::Admin")))]
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RoleGuard {
+    pub role: Role,
+}
+
+#[async_trait]
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> FieldResult<()> {
+        let viewer = ctx.require_viewer()?;
+        if viewer.user.role.includes(self.role) {
+            Ok(())
+        } else {
+            Err(forbidden())
+        }
+    }
+}
+
+/// Whether the viewer is `user`, or has a role that includes `role`.
+pub(super) fn is_owner_or_role(
+    ctx: &Context<'_>,
+    user: &Record<User>,
+    role: Role,
+) -> bool {
+    match ctx.viewer() {
+        Some(viewer) => viewer_is_owner_or_role(viewer, &user.id(), role),
+        None => false,
+    }
+}
+
+/// Whether `viewer` is the user with ID `user`, or has a role that includes
+/// `role`.
+pub(super) fn viewer_is_owner_or_role(
+    viewer: &Viewer,
+    user: &UserId,
+    role: Role,
+) -> bool {
+    &viewer.user.id() == user || viewer.user.role.includes(role)
+}
+
+/// Promotes `user` to admin if they have verified one of the emails in
+/// `Settings::admin_emails`, returning whether they were promoted.
+pub(super) fn promote_if_admin_email(
+    settings: &Settings,
+    user: &mut User,
+) -> bool {
+    if user.role == Role::Admin
+        || user.email_verified_at.is_none()
+        || !settings.is_admin_email(&user.email)
+    {
+        return false;
+    }
+    user.role = Role::Admin;
+    true
+}
@@ -1,3 +1,16 @@
-use graphql::EmptySubscription;
+use super::*;
 
-pub type Subscription = EmptySubscription;
+#[derive(Debug, Clone, Copy, MergedSubscription)]
+pub struct Subscription(UserSubscription);
+
+impl Subscription {
+    pub fn new() -> Self {
+        Self(UserSubscription)
+    }
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self::new()
+    }
+}
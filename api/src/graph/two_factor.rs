@@ -0,0 +1,200 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TwoFactorMutation;
+
+#[Object]
+impl TwoFactorMutation {
+    /// Starts enrolling the viewer in TOTP two-factor authentication. The
+    /// enrollment takes effect once confirmed with `confirmTotp`.
+    async fn enroll_totp(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<EnrollTotpPayload> {
+        let Viewer { mut user, .. } = ctx.require_session()?.to_owned();
+        if matches!(&user.totp, Some(totp) if totp.is_enabled()) {
+            let error =
+                FieldError::new("two-factor authentication already enabled");
+            return Err(error);
+        }
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let totp = Totp::new();
+        let uri = {
+            let issuer = services
+                .settings()
+                .web_public_url
+                .host_str()
+                .unwrap_or("template");
+            totp.secret.uri(issuer, user.email.as_str())
+        };
+        let secret = totp.secret.as_str().to_owned();
+        user.totp = Some(totp);
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = EnrollTotpPayload { secret, uri };
+        Ok(payload)
+    }
+
+    /// Confirms a pending TOTP enrollment with a first code from the
+    /// authenticator app, and returns a set of one-time recovery codes.
+    async fn confirm_totp(
+        &self,
+        ctx: &Context<'_>,
+        code: String,
+    ) -> FieldResult<ConfirmTotpPayload> {
+        let Viewer { mut user, .. } = ctx.require_session()?.to_owned();
+        let mut totp = match user.totp.clone() {
+            Some(totp) if !totp.is_enabled() => totp,
+            _ => {
+                let error = FieldError::new("no pending enrollment");
+                return Err(error);
+            }
+        };
+        if !totp.verify(&code).into_field_result()? {
+            let error = FieldError::new("incorrect code");
+            return Err(error);
+        }
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        totp.enabled_at = Some(now());
+        let recovery_codes = totp.generate_recovery_codes();
+        user.totp = Some(totp);
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = ConfirmTotpPayload {
+            user: UserObject::for_viewer(user),
+            recovery_codes,
+        };
+        Ok(payload)
+    }
+
+    /// Disables two-factor authentication for the viewer, given a code from
+    /// their authenticator app or a recovery code.
+    async fn disable_totp(
+        &self,
+        ctx: &Context<'_>,
+        code: String,
+    ) -> FieldResult<DisableTotpPayload> {
+        let Viewer { mut user, .. } = ctx.require_session()?.to_owned();
+        let mut totp = match user.totp.clone() {
+            Some(totp) => totp,
+            None => {
+                let error =
+                    FieldError::new("two-factor authentication not enabled");
+                return Err(error);
+            }
+        };
+        if totp.is_enabled() && !totp.authenticate(&code).into_field_result()? {
+            let error = FieldError::new("incorrect code");
+            return Err(error);
+        }
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        user.totp = None;
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = DisableTotpPayload {
+            user: UserObject::for_viewer(user),
+        };
+        Ok(payload)
+    }
+
+    /// Finishes logging in with a challenge from `login`, given a code from
+    /// the user's authenticator app or a recovery code.
+    ///
+    /// A challenge can only be attempted once; after an incorrect code, the
+    /// user must log in again.
+    async fn verify_two_factor_login(
+        &self,
+        ctx: &Context<'_>,
+        input: VerifyTwoFactorLoginInput,
+    ) -> FieldResult<LoginPayload> {
+        let VerifyTwoFactorLoginInput { challenge, code } = input;
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        let challenge: Token = challenge
+            .parse()
+            .map_err(|_| FieldError::new("invalid or expired challenge"))?;
+        let user_token = UserToken::redeem(
+            &challenge,
+            UserTokenKind::TwoFactorChallenge,
+            &entity_ctx,
+        )
+        .await
+        .into_field_result()?
+        .ok_or_else(|| FieldError::new("invalid or expired challenge"))?;
+        let mut user = User::get(user_token.user.clone())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to load user")
+            .into_field_result()?
+            .ok_or_else(|| FieldError::new("invalid or expired challenge"))?;
+
+        // Verify code, saving consumed recovery codes and used time steps
+        let mut totp = match user.totp.clone() {
+            Some(totp) if totp.is_enabled() => totp,
+            _ => return start_session(ctx, user).await,
+        };
+        let verified = totp.authenticate(&code).into_field_result()?;
+        if verified {
+            user.totp = Some(totp);
+            user.save(&entity_ctx)
+                .await
+                .context("failed to save user")
+                .into_field_result()?;
+        } else {
+            let error = FieldError::new("incorrect code");
+            return Err(error);
+        }
+
+        start_session(ctx, user).await
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct EnrollTotpPayload {
+    /// The base32-encoded secret, for manual entry into authenticator apps.
+    pub secret: String,
+
+    /// An `otpauth://` URI, for rendering as a QR code.
+    pub uri: Url,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct ConfirmTotpPayload {
+    pub user: UserObject,
+
+    /// One-time codes that can be used in place of an authenticator app.
+    /// These are only ever shown once.
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct DisableTotpPayload {
+    pub user: UserObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct VerifyTwoFactorLoginInput {
+    pub challenge: String,
+    pub code: String,
+}
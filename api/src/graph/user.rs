@@ -1,8 +1,55 @@
 use super::*;
 
-#[derive(Debug, Clone, From)]
+use services::ChangeKind;
+
+use ::bson::Bson;
+use futures::Stream;
+use futures_util::stream::StreamExt;
+use http::header::SET_COOKIE;
+
+#[derive(Debug, Clone)]
 pub(super) struct UserObject {
     pub record: Record<User>,
+
+    /// Whether this user is known to be the one making the request, even if
+    /// the request isn't authenticated as them (i.e. when returning the
+    /// result of a signup or login).
+    pub is_viewer: bool,
+}
+
+impl From<Record<User>> for UserObject {
+    fn from(record: Record<User>) -> Self {
+        UserObject {
+            record,
+            is_viewer: false,
+        }
+    }
+}
+
+impl UserObject {
+    /// Builds a `UserObject` for a user that is known to be the one making
+    /// the request.
+    pub fn for_viewer(record: Record<User>) -> Self {
+        UserObject {
+            record,
+            is_viewer: true,
+        }
+    }
+
+    /// Whether private fields should be shown in full, which they only are
+    /// to the user themself or to admins.
+    fn shows_private(&self, ctx: &Context<'_>) -> bool {
+        self.is_viewer || is_owner_or_role(ctx, &self.record, Role::Admin)
+    }
+
+    /// Renders a private field, masking it unless `shows_private`.
+    fn private(&self, ctx: &Context<'_>, value: &impl Mask) -> String {
+        if self.shows_private(ctx) {
+            value.to_string()
+        } else {
+            value.masked()
+        }
+    }
 }
 
 #[Object(name = "User")]
@@ -19,16 +66,46 @@ impl UserObject {
         self.record.updated_at().into()
     }
 
+    async fn handle(&self) -> &str {
+        self.record.handle.as_str()
+    }
+
     async fn name(&self) -> &str {
         &self.record.name
     }
 
-    async fn email(&self) -> &str {
-        self.record.email.as_str()
+    async fn role(&self) -> RoleEnum {
+        self.record.role.into()
+    }
+
+    async fn email(&self, ctx: &Context<'_>) -> String {
+        self.private(ctx, &self.record.email)
+    }
+
+    async fn email_verified(&self) -> bool {
+        self.record.email_verified_at.is_some()
+    }
+
+    async fn phone(&self, ctx: &Context<'_>) -> PhoneObject {
+        PhoneObject {
+            phone: self.record.phone.clone(),
+            masked: !self.shows_private(ctx),
+        }
+    }
+
+    async fn phone_verified(&self) -> bool {
+        self.record.phone_verified_at.is_some()
     }
 
-    async fn phone(&self) -> &str {
-        self.record.phone.as_str()
+    /// Whether the user has two-factor authentication enabled; only visible
+    /// to the user themself and admins.
+    async fn two_factor_enabled(&self, ctx: &Context<'_>) -> Option<bool> {
+        if !self.shows_private(ctx) {
+            return None;
+        }
+        let enabled =
+            matches!(&self.record.totp, Some(totp) if totp.is_enabled());
+        Some(enabled)
     }
 }
 
@@ -37,21 +114,786 @@ pub(super) struct UserQuery;
 
 #[Object]
 impl UserQuery {
+    /// Lists users, as a Relay connection.
+    #[graphql(guard(RoleGuard(role = "Role::Admin")))]
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+        order_by: Option<UserOrder>,
+        filter: Option<UserFilter>,
+    ) -> FieldResult<EntityConnection<UserObject>> {
+        let conditions = {
+            let UserFilter {
+                handle,
+                email,
+                phone,
+                role,
+            } = filter.unwrap_or_default();
+            let handle = handle
+                .map(|handle| Handle::from_str(&handle))
+                .transpose()
+                .context("invalid handle")
+                .into_field_result()?;
+            let email = email
+                .map(Email::try_from)
+                .transpose()
+                .context("invalid email")
+                .into_field_result()?;
+            let services = ctx.services();
+            let region = &services.settings().default_phone_region;
+            let phone = phone
+                .map(|phone| Phone::parse(&phone, region))
+                .transpose()
+                .into_field_result()?;
+            UserConditions::builder()
+                .handle(handle)
+                .email(email)
+                .phone(phone)
+                .role(role.map(Role::from))
+                .build()
+        };
+        let sorting = order_by.map(|order| {
+            let UserOrder { field, direction } = order;
+            let direction = direction.into();
+            match field {
+                UserOrderField::Name => UserSorting::Name(direction),
+            }
+        });
+        paginate(ctx, Some(conditions), sorting, after, before, first, last)
+            .await
+    }
+
     async fn user(
         &self,
         ctx: &Context<'_>,
         id: Id<User>,
     ) -> FieldResult<Option<UserObject>> {
+        let user = load_user(ctx, id.into()).await?;
+        let user = user.map(UserObject::from);
+        Ok(user)
+    }
+
+    /// Finds a user by their handle, or by a handle they have recently
+    /// changed away from.
+    async fn user_by_handle(
+        &self,
+        ctx: &Context<'_>,
+        handle: String,
+    ) -> FieldResult<Option<UserByHandleResult>> {
+        let handle = match Handle::from_str(&handle) {
+            Ok(handle) => handle,
+            Err(_) => return Ok(None),
+        };
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services);
+
+        let user = User::find_by_handle(handle.clone())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to lookup user by handle")
+            .into_field_result()?;
+        if let Some(user) = user {
+            let result = UserByHandleResult {
+                user: user.into(),
+                redirected: false,
+            };
+            return Ok(Some(result));
+        }
+
+        // Fall back to former handles
+        let former_handle = FormerHandle::find_reserved(handle, &entity_ctx)
+            .await
+            .into_field_result()?;
+        let former_handle = match former_handle {
+            Some(former_handle) => former_handle,
+            None => return Ok(None),
+        };
+        let user = load_user(ctx, former_handle.user.clone()).await?;
+        let result = user.map(|user| UserByHandleResult {
+            user: user.into(),
+            redirected: true,
+        });
+        Ok(result)
+    }
+
+    /// Whether `handle` is valid and free to take, either by a new user or by
+    /// the viewer.
+    async fn handle_available(
+        &self,
+        ctx: &Context<'_>,
+        handle: String,
+    ) -> FieldResult<bool> {
+        let viewer = ctx.viewer().map(|viewer| viewer.user.id());
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let mut errors = ValidationErrors::new();
+        let handle =
+            match check_handle(services.settings(), &mut errors, &handle) {
+                Some(handle) => handle,
+                None => return Ok(false),
+            };
+        validate_unique(
+            &entity_ctx,
+            &mut errors,
+            Some(&handle),
+            None,
+            None,
+            viewer.as_ref(),
+        )
+        .await
+        .into_field_result()?;
+        Ok(errors.is_empty())
+    }
+
+    /// The currently authenticated user, if any.
+    async fn viewer(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Option<UserObject>> {
+        let id = match ctx.viewer() {
+            Some(viewer) => viewer.user.id(),
+            None => return Ok(None),
+        };
+        let user = load_user(ctx, id).await?;
+        let user = user.map(UserObject::from);
+        Ok(user)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct UserSubscription;
+
+#[Subscription]
+impl UserSubscription {
+    /// Emits the user with `id` whenever they are updated, until they are
+    /// deleted.
+    ///
+    /// Only the user themselves and admins may subscribe, and only for as
+    /// long as their credential remains valid.
+    async fn user_updated(
+        &self,
+        ctx: &Context<'_>,
+        id: Id<User>,
+    ) -> FieldResult<impl Stream<Item = UserObject>> {
+        let id: UserId = id.into();
+        let viewer = ctx.require_viewer()?.to_owned();
+        if !viewer_is_owner_or_role(&viewer, &id, Role::Admin) {
+            return Err(forbidden());
+        }
+
+        let services = ctx.services();
+        let changes = services.watcher().watch::<User>();
+
+        let target: Bson = id.clone().into();
+        let updates = changes
+            .filter(move |change| {
+                let matches = change.id == target;
+                async move { matches }
+            })
+            .take_while(|change| {
+                let deleted = change.kind == ChangeKind::Delete;
+                async move { !deleted }
+            })
+            .then(move |_| {
+                let id = id.clone();
+                let viewer = viewer.clone();
+                let ctx = EntityContext::new(services.clone());
+                async move {
+                    // Re-check the viewer, whose credential may have been
+                    // revoked (or role changed) since they subscribed
+                    let allowed = match viewer.reload(&ctx).await? {
+                        Some(viewer) => {
+                            viewer_is_owner_or_role(&viewer, &id, Role::Admin)
+                        }
+                        None => false,
+                    };
+                    if !allowed {
+                        return Ok(None);
+                    }
+                    User::get(id)
+                        .optional()
+                        .load(&ctx)
+                        .await
+                        .context("failed to load user")
+                }
+            })
+            // Stop once the viewer may no longer see the user, or the user
+            // can no longer be found (i.e. if they were soft-deleted)
+            .take_while(|user| {
+                let missing = matches!(user, Ok(None));
+                async move { !missing }
+            })
+            .filter_map(|user| async move {
+                match user {
+                    Ok(user) => user.map(UserObject::from),
+                    Err(error) => {
+                        error!(
+                            target: "template_api::graph",
+                            error = %format!("{:#}", error),
+                            "failed to load updated user",
+                        );
+                        None
+                    }
+                }
+            });
+        Ok(updates)
+    }
+
+    /// Emits each user that signs up from now on.
+    #[graphql(guard(RoleGuard(role = "Role::Admin")))]
+    async fn user_signed_up(
+        &self,
+        ctx: &Context<'_>,
+    ) -> impl Stream<Item = UserObject> {
+        let services = ctx.services();
+        let events = services.broker().subscribe::<UserSignedUp>();
+        events.filter_map(move |event| {
+            let UserSignedUp { user } = event;
+            let ctx = EntityContext::new(services.clone());
+            async move {
+                let user = User::get(user).optional().load(&ctx).await;
+                match user {
+                    Ok(user) => user.map(UserObject::from),
+                    Err(error) => {
+                        error!(
+                            target: "template_api::graph",
+                            error = %format!("{:#}", error),
+                            "failed to load new user",
+                        );
+                        None
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, InputObject)]
+pub(super) struct UserFilter {
+    pub handle: Option<String>,
+    pub email: Option<String>,
+
+    /// A phone number, in international format or else assumed to be in
+    /// the server's default region.
+    pub phone: Option<String>,
+
+    pub role: Option<RoleEnum>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct UserOrder {
+    pub field: UserOrderField,
+    pub direction: SortingDirectionEnum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub(super) enum UserOrderField {
+    Name,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct UserByHandleResult {
+    pub user: UserObject,
+
+    /// Whether the handle was one that the user has since changed away
+    /// from, in which case clients should redirect to their current handle.
+    pub redirected: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct UserMutation;
+
+#[Object]
+impl UserMutation {
+    async fn signup(
+        &self,
+        ctx: &Context<'_>,
+        input: SignupInput,
+    ) -> FieldResult<SignupPayload> {
+        let SignupInput {
+            handle,
+            name,
+            email,
+            phone,
+            phone_region,
+            password,
+        } = input;
+
+        let services = ctx.services();
+        let ctx = EntityContext::new(services.clone());
+
+        // Validate input
+        let mut errors = ValidationErrors::new();
+        let handle = check_handle(services.settings(), &mut errors, &handle);
+        let name = {
+            let name = name.trim().to_owned();
+            if name.is_empty() {
+                errors.push("name", "must not be empty");
+            }
+            name
+        };
+        let email = errors.check("email", Email::try_from(email));
+        let phone = check_phone(
+            services.settings(),
+            &mut errors,
+            &phone,
+            phone_region.as_deref(),
+        );
+        let password = match password {
+            Some(password) => {
+                errors.check("password", Password::new(password)).map(Some)
+            }
+            None => Some(None),
+        };
+
+        // Ensure handle, email, and phone are not already taken
+        validate_unique(
+            &ctx,
+            &mut errors,
+            handle.as_ref(),
+            email.as_ref(),
+            phone.as_ref(),
+            None,
+        )
+        .await
+        .into_field_result()?;
+
+        let (handle, email, phone, password) =
+            match (handle, email, phone, password) {
+                (Some(handle), Some(email), Some(phone), Some(password))
+                    if errors.is_empty() =>
+                {
+                    (handle, email, phone, password)
+                }
+                _ => {
+                    let payload = SignupPayload {
+                        user: None,
+                        errors: errors.into(),
+                    };
+                    return Ok(payload);
+                }
+            };
+
+        // Hash password
+        let password = match password {
+            Some(password) => {
+                let hash = spawn_blocking(move || PasswordHash::new(&password))
+                    .await
+                    .context("failed to join password hashing task")
+                    .into_field_result()?
+                    .into_field_result()?;
+                Some(hash)
+            }
+            None => None,
+        };
+
+        // Create user
+        let user = User::builder()
+            .handle(handle)
+            .name(name)
+            .email(email)
+            .phone(phone)
+            .password(password)
+            .build();
+        let mut user = Record::new(user);
+        user.save(&ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let event = UserSignedUp { user: user.id() };
+        if let Err(error) = services.broker().publish(&event).await {
+            error!(
+                target: "template_api::graph",
+                user = %user.id(),
+                error = %format!("{:#}", error),
+                "failed to publish signup",
+            );
+        }
+
+        let payload = SignupPayload {
+            user: Some(UserObject::for_viewer(user)),
+            errors: default(),
+        };
+        Ok(payload)
+    }
+
+    #[graphql(guard(RoleGuard(role = "Role::Admin")))]
+    async fn set_user_role(
+        &self,
+        ctx: &Context<'_>,
+        input: SetUserRoleInput,
+    ) -> FieldResult<SetUserRolePayload> {
+        let SetUserRoleInput { user_id, role } = input;
+
         let services = ctx.services();
         let ctx = EntityContext::new(services);
 
-        let user = User::get(id.into())
+        let user = User::get(user_id.into())
             .optional()
             .load(&ctx)
             .await
             .context("failed to load user")
             .into_field_result()?;
-        let user = user.map(UserObject::from);
-        Ok(user)
+        let mut user = match user {
+            Some(user) => user,
+            None => {
+                let error = FieldError::new("user not found");
+                return Err(error);
+            }
+        };
+        user.role = role.into();
+        user.save(&ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = SetUserRolePayload { user: user.into() };
+        Ok(payload)
+    }
+
+    /// Updates the viewer's profile. Changing their email or phone resets
+    /// its verification status, and changing their handle reserves the old
+    /// one for them for a while.
+    async fn update_user(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateUserInput,
+    ) -> FieldResult<UpdateUserPayload> {
+        let UpdateUserInput {
+            name,
+            handle,
+            email,
+            phone,
+            phone_region,
+        } = input;
+        let viewer = ctx.require_viewer()?;
+        let session_check = ctx.require_session().map(|_| ());
+        let mut user = viewer.user.clone();
+
+        let services = ctx.services();
+        let ctx = EntityContext::new(services.clone());
+
+        // Validate input
+        let mut errors = ValidationErrors::new();
+        let name = name.and_then(|name| {
+            let name = name.trim().to_owned();
+            if name.is_empty() {
+                errors.push("name", "must not be empty");
+                return None;
+            }
+            Some(name)
+        });
+        // Skip an unchanged handle, so that users holding a handle that has
+        // since been reserved can still update the rest of their profile
+        let handle = handle
+            .filter(|handle| {
+                Handle::new(handle).ok().as_ref() != Some(&user.handle)
+            })
+            .and_then(|handle| {
+                check_handle(services.settings(), &mut errors, &handle)
+            });
+        let email = email
+            .and_then(|email| errors.check("email", Email::try_from(email)))
+            .filter(|email| email != &user.email);
+        let phone = phone
+            .and_then(|phone| {
+                check_phone(
+                    services.settings(),
+                    &mut errors,
+                    &phone,
+                    phone_region.as_deref(),
+                )
+            })
+            .filter(|phone| phone != &user.phone);
+
+        // Changing the handle or email (where password resets are sent)
+        // requires a session, so that an API key can't be used to take over
+        // the account
+        if handle.is_some() || email.is_some() {
+            session_check?;
+        }
+
+        // Ensure handle, email, and phone are not already taken
+        validate_unique(
+            &ctx,
+            &mut errors,
+            handle.as_ref(),
+            email.as_ref(),
+            phone.as_ref(),
+            Some(&user.id()),
+        )
+        .await
+        .into_field_result()?;
+
+        if !errors.is_empty() {
+            let payload = UpdateUserPayload {
+                user: None,
+                errors: errors.into(),
+            };
+            return Ok(payload);
+        }
+
+        // Apply changes
+        if let Some(name) = name {
+            user.name = name;
+        }
+        if let Some(handle) = handle {
+            // Taking back a former handle releases it, so that it no longer
+            // redirects
+            FormerHandle::release(user.id(), handle.clone(), &ctx)
+                .await
+                .into_field_result()?;
+            let former_handle =
+                FormerHandle::new(user.id(), user.handle.clone());
+            let mut former_handle = Record::new(former_handle);
+            former_handle
+                .save(&ctx)
+                .await
+                .context("failed to save former handle")
+                .into_field_result()?;
+            user.handle = handle;
+        }
+        if let Some(email) = email {
+            user.email = email;
+            user.email_verified_at = None;
+            UserToken::revoke_all(
+                user.id(),
+                UserTokenKind::EmailVerification,
+                &ctx,
+            )
+            .await
+            .into_field_result()?;
+        }
+        if let Some(phone) = phone {
+            user.phone = phone;
+            user.phone_verified_at = None;
+        }
+        user.save(&ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = UpdateUserPayload {
+            user: Some(UserObject::for_viewer(user)),
+            errors: default(),
+        };
+        Ok(payload)
+    }
+
+    /// Deletes the viewer's account, logging them out everywhere. The
+    /// account is purged permanently after a grace period.
+    async fn delete_account(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<DeleteAccountPayload> {
+        let viewer = ctx.require_session()?;
+        let session = viewer.session().expect("viewer should have a session");
+        let mut user = viewer.user.clone();
+
+        // Clear session cookie
+        let services = ctx.services();
+        let cookie = session_cookie(services.settings(), None, session);
+        ctx.insert_http_header(SET_COOKIE, cookie);
+
+        let ctx = EntityContext::new(services);
+
+        user.deleted_at = Some(now());
+        user.save(&ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+        Session::revoke_all(user.id(), &ctx)
+            .await
+            .into_field_result()?;
+        ApiKey::revoke_all(user.id(), &ctx)
+            .await
+            .into_field_result()?;
+        for kind in UserTokenKind::ALL {
+            UserToken::revoke_all(user.id(), kind, &ctx)
+                .await
+                .into_field_result()?;
+        }
+
+        Ok(DeleteAccountPayload { ok: true })
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct SetUserRoleInput {
+    pub user_id: Id<User>,
+    pub role: RoleEnum,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct SetUserRolePayload {
+    pub user: UserObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct UpdateUserInput {
+    pub name: Option<String>,
+    pub handle: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+
+    /// The region to assume for `phone` if it lacks a country code, instead
+    /// of the server's default.
+    pub phone_region: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct UpdateUserPayload {
+    pub user: Option<UserObject>,
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct DeleteAccountPayload {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub(super) struct SignupInput {
+    pub handle: String,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+
+    /// The region to assume for `phone` if it lacks a country code, instead
+    /// of the server's default.
+    pub phone_region: Option<String>,
+
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct SignupPayload {
+    pub user: Option<UserObject>,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Loads the user with `id` through the operation's `EntityLoader`, unless
+/// they have been deleted.
+pub(super) async fn load_user(
+    ctx: &Context<'_>,
+    id: UserId,
+) -> FieldResult<Option<Record<User>>> {
+    let user = load_entity::<User>(ctx, id)
+        .await
+        .context("failed to load user")
+        .into_field_result()?;
+    let user = user.filter(|user| !user.is_deleted());
+    Ok(user)
+}
+
+/// Parses `handle`, recording a validation error if it is invalid or
+/// reserved.
+fn check_handle(
+    settings: &Settings,
+    errors: &mut ValidationErrors,
+    handle: &str,
+) -> Option<Handle> {
+    let handle = errors.check("handle", Handle::new(handle))?;
+    if settings.is_handle_reserved(&handle) {
+        errors.push("handle", "reserved");
+        return None;
+    }
+    Some(handle)
+}
+
+/// Parses `phone`, assuming it belongs to `region` (or the default region)
+/// if it lacks a country code, and recording a validation error if it is
+/// invalid.
+fn check_phone(
+    settings: &Settings,
+    errors: &mut ValidationErrors,
+    phone: &str,
+    region: Option<&str>,
+) -> Option<Phone> {
+    let region = match region {
+        Some(region) => {
+            errors.check("phoneRegion", PhoneRegion::from_str(region))?
+        }
+        None => settings.default_phone_region.clone(),
+    };
+    errors.check("phone", Phone::parse(phone, &region))
+}
+
+/// Records a validation error for each of `handle`, `email`, and `phone`
+/// that already belongs to a user other than `except`.
+///
+/// Deleted users are considered too, since they hold onto their handle,
+/// email, and phone until they are purged. So are handles that another user
+/// has recently changed away from.
+async fn validate_unique(
+    ctx: &EntityContext,
+    errors: &mut ValidationErrors,
+    handle: Option<&Handle>,
+    email: Option<&Email>,
+    phone: Option<&Phone>,
+    except: Option<&UserId>,
+) -> Result<()> {
+    let is_taken = |user: Option<Record<User>>| match (user, except) {
+        (Some(user), Some(except)) => &user.id() != except,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    if let Some(handle) = handle {
+        let conditions = UserConditions::builder()
+            .handle(handle.clone())
+            .deleted(None)
+            .build();
+        let existing = User::find_one(conditions)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to lookup user by handle")?;
+        let reserved = FormerHandle::find_reserved(handle.clone(), ctx).await?;
+        let is_reserved = match (reserved, except) {
+            (Some(reserved), Some(except)) => &reserved.user != except,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if is_taken(existing) || is_reserved {
+            errors.push("handle", "already taken");
+        }
+    }
+    if let Some(email) = email {
+        let conditions = UserConditions::builder()
+            .email(email.clone())
+            .deleted(None)
+            .build();
+        let existing = User::find_one(conditions)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to lookup user by email")?;
+        if is_taken(existing) {
+            errors.push("email", "already in use");
+        }
+    }
+    if let Some(phone) = phone {
+        let conditions = UserConditions::builder()
+            .phone(phone.clone())
+            .deleted(None)
+            .build();
+        let existing = User::find_one(conditions)
+            .optional()
+            .load(ctx)
+            .await
+            .context("failed to lookup user by phone")?;
+        if is_taken(existing) {
+            errors.push("phone", "already in use");
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,59 @@
+use super::*;
+
+/// A `ValidationError` describes why an input field was rejected, so that
+/// clients can render it next to the corresponding form input.
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct ValidationError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: &str, message: impl Display) -> Self {
+        ValidationError {
+            field: field.to_owned(),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct ValidationErrors(Vec<ValidationError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        default()
+    }
+
+    pub fn push(&mut self, field: &str, message: impl Display) {
+        let error = ValidationError::new(field, message);
+        self.0.push(error);
+    }
+
+    /// Unwraps a parse result, recording its error against `field` on
+    /// failure.
+    pub fn check<T, E: Display>(
+        &mut self,
+        field: &str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.push(field, error);
+                None
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<ValidationErrors> for Vec<ValidationError> {
+    fn from(errors: ValidationErrors) -> Self {
+        let ValidationErrors(errors) = errors;
+        errors
+    }
+}
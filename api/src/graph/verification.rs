@@ -0,0 +1,253 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct VerificationMutation;
+
+#[Object]
+impl VerificationMutation {
+    /// Sends (or re-sends) a link to the viewer's email, which proves they
+    /// own it once redeemed with `verifyEmail`.
+    async fn send_verification_email(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<SendVerificationEmailPayload> {
+        let Viewer { user, .. } = ctx.require_viewer()?.to_owned();
+        if user.email_verified_at.is_some() {
+            let error = FieldError::new("email already verified");
+            return Err(error);
+        }
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        // Mint verification token
+        let (user_token, token) =
+            UserToken::new(user.id(), UserTokenKind::EmailVerification);
+        let mut user_token = Record::new(user_token);
+        user_token
+            .save(&entity_ctx)
+            .await
+            .context("failed to save verification token")
+            .into_field_result()?;
+
+        // Send verification link
+        let link = {
+            let mut url = services
+                .settings()
+                .web_public_url_for("verify/email")
+                .into_field_result()?;
+            url.query_pairs_mut().append_pair("token", token.as_str());
+            url
+        };
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Verify your email".to_owned(),
+            body: format!(
+                "Hi {}, use the following link to verify your email: {}",
+                &user.name, &link,
+            ),
+        };
+        services
+            .mailer()
+            .send(mail)
+            .await
+            .context("failed to send verification email")
+            .into_field_result()?;
+
+        Ok(SendVerificationEmailPayload { ok: true })
+    }
+
+    async fn verify_email(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> FieldResult<VerifyEmailPayload> {
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        let token: Token = token.parse().map_err(|_| {
+            FieldError::new("invalid or expired verification link")
+        })?;
+        let user_token = UserToken::redeem(
+            &token,
+            UserTokenKind::EmailVerification,
+            &entity_ctx,
+        )
+        .await
+        .into_field_result()?
+        .ok_or_else(|| {
+            FieldError::new("invalid or expired verification link")
+        })?;
+
+        // Mark email as verified
+        let mut user = User::get(user_token.user.clone())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to load user")
+            .into_field_result()?
+            .ok_or_else(|| {
+                FieldError::new("invalid or expired verification link")
+            })?;
+        if user.email_verified_at.is_none() {
+            user.email_verified_at = Some(now());
+            promote_if_admin_email(services.settings(), &mut user);
+            user.save(&entity_ctx)
+                .await
+                .context("failed to save user")
+                .into_field_result()?;
+        }
+
+        let payload = VerifyEmailPayload {
+            user: UserObject::for_viewer(user),
+        };
+        Ok(payload)
+    }
+
+    /// Sends a one-time code to the viewer's phone, which proves they own it
+    /// once entered with `verifyPhoneCode`.
+    async fn send_phone_code(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<SendPhoneCodePayload> {
+        let Viewer { user, .. } = ctx.require_viewer()?.to_owned();
+        if user.phone_verified_at.is_some() {
+            let error = FieldError::new("phone already verified");
+            return Err(error);
+        }
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        // Replace pending verification, carrying over its failed attempts so
+        // that re-sending a code doesn't grant more guesses
+        let existing = PhoneVerification::find_by_user(user.id())
+            .optional()
+            .load(&entity_ctx)
+            .await
+            .context("failed to load pending phone verification")
+            .into_field_result()?;
+        let mut attempts = 0;
+        if let Some(existing) = existing {
+            if !existing.is_expired() {
+                let resend_at = existing.created_at()
+                    + PhoneVerification::resend_cooldown();
+                if now() < resend_at {
+                    let error = FieldError::new(
+                        "code sent too recently; try again in a minute",
+                    );
+                    return Err(error);
+                }
+                if existing.attempts >= PhoneVerification::MAX_ATTEMPTS {
+                    let error = FieldError::new(
+                        "too many incorrect codes; try again later",
+                    );
+                    return Err(error);
+                }
+                attempts = existing.attempts;
+            }
+            existing
+                .delete(&entity_ctx)
+                .await
+                .context("failed to delete pending phone verification")
+                .into_field_result()?;
+        }
+
+        // Create verification
+        let (mut verification, code) = PhoneVerification::new(
+            user.id(),
+            user.phone.clone(),
+            &services.settings().secret,
+        );
+        verification.attempts = attempts;
+        let mut verification = Record::new(verification);
+        verification
+            .save(&entity_ctx)
+            .await
+            .context("failed to save phone verification")
+            .into_field_result()?;
+
+        // Send code
+        let sms = Sms {
+            to: user.phone.clone(),
+            body: format!("Your verification code is {}.", code),
+        };
+        services
+            .sms_sender()
+            .send(sms)
+            .await
+            .context("failed to send verification code")
+            .into_field_result()?;
+
+        Ok(SendPhoneCodePayload { ok: true })
+    }
+
+    async fn verify_phone_code(
+        &self,
+        ctx: &Context<'_>,
+        code: String,
+    ) -> FieldResult<VerifyPhoneCodePayload> {
+        let Viewer { mut user, .. } = ctx.require_viewer()?.to_owned();
+
+        let services = ctx.services();
+        let entity_ctx = EntityContext::new(services.clone());
+
+        // Count the attempt before checking the code, so that concurrent
+        // guesses can't exceed the limit
+        let verification = PhoneVerification::attempt(user.id(), &entity_ctx)
+            .await
+            .into_field_result()?;
+        let verification = match verification {
+            Some(verification) if verification.phone == user.phone => {
+                verification
+            }
+            _ => {
+                let error = FieldError::new("code expired");
+                return Err(error);
+            }
+        };
+
+        // Check code
+        if !verification.matches(&code, &services.settings().secret) {
+            let error = FieldError::new("incorrect code");
+            return Err(error);
+        }
+        verification
+            .delete(&entity_ctx)
+            .await
+            .context("failed to delete phone verification")
+            .into_field_result()?;
+
+        // Mark phone as verified
+        user.phone_verified_at = Some(now());
+        user.save(&entity_ctx)
+            .await
+            .context("failed to save user")
+            .into_field_result()?;
+
+        let payload = VerifyPhoneCodePayload {
+            user: UserObject::for_viewer(user),
+        };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct SendVerificationEmailPayload {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct VerifyEmailPayload {
+    pub user: UserObject,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct SendPhoneCodePayload {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub(super) struct VerifyPhoneCodePayload {
+    pub user: UserObject,
+}
@@ -1,12 +1,16 @@
+mod data_export;
 mod graphql;
 mod graphql_playground;
+mod viewer;
 
 pub use self::graphql::*;
+pub use data_export::*;
 pub use graphql_playground::*;
+pub use viewer::*;
 
 use super::*;
 
-use entities::*;
+use entities::{Context as EntityContext, *};
 use services::Services;
 
 use entrust::Comparison;
@@ -28,6 +32,9 @@ pub type HandlerResult<T> = Result<T, HandlerError>;
 
 #[derive(Debug, Error)]
 pub enum HandlerError {
+    #[error("{0}")]
+    Forbidden(String),
+
     #[error(transparent)]
     Other(#[from] Error),
 }
@@ -39,6 +46,7 @@ impl IntoResponse for HandlerError {
     fn into_response(self) -> Response<Self::Body> {
         use HandlerError::*;
         let (status_code, message) = match self {
+            Forbidden(message) => (StatusCode::FORBIDDEN, message),
             Other(error) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", &error))
             }
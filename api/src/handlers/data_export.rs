@@ -0,0 +1,61 @@
+use super::*;
+
+use http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use http::Uri;
+
+/// Serves a `DataExport` as a JSON attachment, given a link from the
+/// `requestDataExport` mutation.
+///
+/// The link may only be used once, and only by the user who requested it.
+pub async fn data_export_handler(
+    Extension(services): Extension<Services>,
+    ViewerExtractor(viewer): ViewerExtractor,
+    uri: Uri,
+) -> HandlerResult<Response<Full<Bytes>>> {
+    let viewer = viewer.ok_or_else(|| {
+        HandlerError::Forbidden("not authenticated".to_owned())
+    })?;
+    let token = uri
+        .query()
+        .and_then(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .find(|(name, _)| name == "token")
+                .map(|(_, value)| value.into_owned())
+        })
+        .and_then(|token| token.parse::<Token>().ok())
+        .ok_or_else(invalid_link)?;
+
+    let ctx = EntityContext::new(services);
+    let user_token = UserToken::redeem_for(
+        &token,
+        UserTokenKind::DataExport,
+        viewer.user.id(),
+        &ctx,
+    )
+    .await?
+    .ok_or_else(invalid_link)?;
+    let user = User::get(user_token.user.clone())
+        .optional()
+        .load(&ctx)
+        .await
+        .context("failed to load user")?
+        .ok_or_else(invalid_link)?;
+
+    let export = DataExport::build(&user, &ctx).await?;
+    let body = serde_json::to_vec_pretty(&export)
+        .context("failed to serialize data export")?;
+    let filename = format!("{}-export.json", &user.handle);
+    let response = Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(Full::from(body))
+        .context("failed to build response")?;
+    Ok(response)
+}
+
+fn invalid_link() -> HandlerError {
+    HandlerError::Forbidden("invalid or expired export link".to_owned())
+}
@@ -1,18 +1,27 @@
 use super::*;
 
-use graph::{Mutation, Query, Subscription};
+use graph::{EntityLoader, Mutation, Query, Subscription};
 
 use axum::extract::ws::WebSocketUpgrade;
 
 use ::graphql::http::ALL_WEBSOCKET_PROTOCOLS as GRAPHQL_WEBSOCKET_PROTOCOLS;
+use ::graphql::parser::parse_query as parse_graphql_query;
+use ::graphql::parser::types::DocumentOperations as GraphQLOperations;
+use ::graphql::parser::types::OperationDefinition;
+use ::graphql::parser::types::OperationType as GraphQLOperationType;
+use ::graphql::parser::Positioned;
+use ::graphql::Data as GraphQLData;
+use ::graphql::Request as GraphQLRequestData;
 use ::graphql::Schema as GraphQLSchema;
 use ::graphql::ServerError as GraphQLError;
 
-use graphql_axum::graphql_subscription;
+use graphql_axum::graphql_subscription_with_data;
 use graphql_axum::GraphQLRequest;
 use graphql_axum::GraphQLResponse;
 use graphql_axum::SecWebsocketProtocol as WebSocketProtocol;
 
+use http::Method;
+
 #[derive(Clone)]
 pub struct GraphQLExtension {
     schema: GraphQLSchema<Query, Mutation, Subscription>,
@@ -28,6 +37,9 @@ impl GraphQLExtension {
 
 pub async fn graphql_handler(
     Extension(extension): Extension<GraphQLExtension>,
+    Extension(services): Extension<Services>,
+    ViewerExtractor(viewer): ViewerExtractor,
+    method: Method,
     request: Option<GraphQLRequest>,
     websocket: Option<WebSocketUpgrade>,
     websocket_protocol: Option<HeaderExtractor<WebSocketProtocol>>,
@@ -40,13 +52,39 @@ pub async fn graphql_handler(
             .protocols(GRAPHQL_WEBSOCKET_PROTOCOLS)
             .on_upgrade(move |websocket| async move {
                 trace!("received WebSocket connection");
-                graphql_subscription(websocket, schema, protocol).await
+                graphql_subscription_with_data(
+                    websocket,
+                    schema,
+                    protocol,
+                    move |_| async move {
+                        let mut data = GraphQLData::default();
+                        if let Some(viewer) = viewer {
+                            data.insert(viewer);
+                        }
+                        Ok(data)
+                    },
+                )
+                .await
             })
             .into_response();
         let (head, body) = response.into_parts();
         return Response::from_parts(head, box_body(body));
     }
-    if let Some(GraphQLRequest(request)) = request {
+    if let Some(GraphQLRequest(mut request)) = request {
+        // Only run mutations on POST, so that they can't be triggered by a
+        // cross-site link
+        //
+        // Persisted queries sent by hash alone can't be checked here, but
+        // cross-site requests don't carry the session cookie to begin with
+        if method != Method::POST && is_mutation(&request) {
+            let response = StatusCode::METHOD_NOT_ALLOWED.into_response();
+            let (head, body) = response.into_parts();
+            return Response::from_parts(head, box_body(body));
+        }
+        if let Some(viewer) = viewer {
+            request = request.data(viewer);
+        }
+        request = request.data(EntityLoader::data_loader(services));
         let response = schema.execute(request).await;
         response
             .errors
@@ -86,3 +124,76 @@ pub async fn graphql_handler(
         Response::from_parts(head, box_body(body))
     }
 }
+
+/// Determines whether `request` contains a mutation.
+///
+/// Any mutation in the document counts, regardless of which operation is
+/// selected, since a lone named operation runs even without an operation
+/// name.
+///
+/// Requests that fail to parse are left for the schema to reject.
+fn is_mutation(request: &GraphQLRequestData) -> bool {
+    let document = match parse_graphql_query(&request.query) {
+        Ok(document) => document,
+        Err(_) => return false,
+    };
+    let is_mutation = |operation: &Positioned<OperationDefinition>| {
+        operation.node.ty == GraphQLOperationType::Mutation
+    };
+    match &document.operations {
+        GraphQLOperations::Single(operation) => is_mutation(operation),
+        GraphQLOperations::Multiple(operations) => {
+            operations.values().any(is_mutation)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        query: &str,
+        operation_name: Option<&str>,
+    ) -> GraphQLRequestData {
+        let request = GraphQLRequestData::new(query);
+        match operation_name {
+            Some(name) => request.operation_name(name),
+            None => request,
+        }
+    }
+
+    #[test]
+    fn detects_anonymous_mutations() {
+        assert!(is_mutation(&request("mutation { logout { ok } }", None)));
+        assert!(!is_mutation(&request("query { viewer { id } }", None)));
+        assert!(!is_mutation(&request("{ viewer { id } }", None)));
+    }
+
+    #[test]
+    fn detects_named_mutations() {
+        let query = "mutation Logout { logout { ok } }";
+        assert!(is_mutation(&request(query, Some("Logout"))));
+
+        // A lone named operation runs without an operation name, too
+        assert!(is_mutation(&request(query, None)));
+    }
+
+    #[test]
+    fn detects_mutations_alongside_queries() {
+        let query = "query Viewer { viewer { id } } \
+            mutation Logout { logout { ok } }";
+        assert!(is_mutation(&request(query, Some("Logout"))));
+        assert!(is_mutation(&request(query, Some("Viewer"))));
+        assert!(is_mutation(&request(query, None)));
+
+        let query = "query Viewer { viewer { id } } \
+            query Build { build { version } }";
+        assert!(!is_mutation(&request(query, Some("Viewer"))));
+    }
+
+    #[test]
+    fn ignores_unparseable_requests() {
+        assert!(!is_mutation(&request("mutation {", None)));
+    }
+}
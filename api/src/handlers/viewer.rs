@@ -0,0 +1,156 @@
+use super::*;
+
+use axum::extract::{FromRequest, RequestParts};
+use http::header::{HeaderMap, AUTHORIZATION, COOKIE};
+
+/// Extracts the `Viewer` for a request from its credentials: either a
+/// session token or API key in an `Authorization: Bearer` header, or a
+/// session cookie.
+#[derive(Debug, Clone)]
+pub struct ViewerExtractor(pub Option<Viewer>);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ViewerExtractor {
+    type Rejection = HandlerError;
+
+    async fn from_request(
+        req: &mut RequestParts<B>,
+    ) -> Result<Self, Self::Rejection> {
+        let services = req
+            .extensions()
+            .and_then(|extensions| extensions.get::<Services>())
+            .cloned()
+            .context("missing services extension")?;
+        let credential = match req.headers().and_then(credential) {
+            Some(credential) => credential,
+            None => return Ok(ViewerExtractor(None)),
+        };
+
+        let ctx = EntityContext::new(services);
+        let viewer = match credential {
+            RequestCredential::SessionToken(token) => {
+                Viewer::from_session_token(&token, &ctx).await
+            }
+            RequestCredential::ApiKey(token) => {
+                Viewer::from_api_key(&token, &ctx).await
+            }
+        };
+        let viewer = viewer.context("failed to resolve viewer")?;
+        Ok(ViewerExtractor(viewer))
+    }
+}
+
+enum RequestCredential {
+    SessionToken(Token),
+    ApiKey(Token),
+}
+
+fn credential(headers: &HeaderMap) -> Option<RequestCredential> {
+    if let Some(authorization) = headers.get(AUTHORIZATION) {
+        let authorization = authorization.to_str().ok()?;
+        let token = authorization.strip_prefix("Bearer ")?.trim();
+        if token.starts_with(ApiKey::KEY_PREFIX) {
+            let token = ApiKey::parse_key(token)?;
+            return Some(RequestCredential::ApiKey(token));
+        }
+        let token = token.parse().ok()?;
+        return Some(RequestCredential::SessionToken(token));
+    }
+    let token = session_cookie(headers)?;
+    Some(RequestCredential::SessionToken(token))
+}
+
+fn session_cookie(headers: &HeaderMap) -> Option<Token> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|cookies| cookies.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| {
+            let (name, value) = cookie.trim().split_once('=')?;
+            if name == Session::COOKIE_NAME {
+                value.parse().ok()
+            } else {
+                None
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            let value = HeaderValue::from_str(value).unwrap();
+            headers.append(name.clone(), value);
+        }
+        headers
+    }
+
+    #[test]
+    fn reads_api_keys_from_bearer() {
+        let token = Token::generate();
+        let authorization = format!("Bearer key_{}", token.as_str());
+        let headers = headers(&[(AUTHORIZATION, &authorization)]);
+        assert!(matches!(
+            credential(&headers),
+            Some(RequestCredential::ApiKey(key)) if key == token
+        ));
+    }
+
+    #[test]
+    fn reads_session_tokens_from_bearer() {
+        let token = Token::generate();
+        let authorization = format!("Bearer {}", token.as_str());
+        let headers = headers(&[(AUTHORIZATION, &authorization)]);
+        assert!(matches!(
+            credential(&headers),
+            Some(RequestCredential::SessionToken(session)) if session == token
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_bearer() {
+        let token = Token::generate();
+        let cookie = format!("session={}", token.as_str());
+        for authorization in ["Bearer", "Bearer short", "Basic abc", "key_"] {
+            // A bad Authorization header isn't bypassed with a cookie
+            let headers =
+                headers(&[(AUTHORIZATION, authorization), (COOKIE, &cookie)]);
+            assert!(credential(&headers).is_none());
+        }
+    }
+
+    #[test]
+    fn reads_session_cookie() {
+        let token = Token::generate();
+        let cookie = format!("theme=dark; session={}; lang=en", token.as_str());
+        let headers = headers(&[(COOKIE, &cookie)]);
+        assert_eq!(session_cookie(&headers), Some(token.clone()));
+        assert!(matches!(
+            credential(&headers),
+            Some(RequestCredential::SessionToken(session)) if session == token
+        ));
+    }
+
+    #[test]
+    fn ignores_malformed_cookies() {
+        let token = Token::generate();
+        let cookies = [
+            "session".to_owned(),
+            "session=".to_owned(),
+            "session=short".to_owned(),
+            format!("sessions={}", token.as_str()),
+            format!("session={}!", token.as_str()),
+        ];
+        for cookie in &cookies {
+            let headers = headers(&[(COOKIE, cookie)]);
+            assert!(session_cookie(&headers).is_none());
+            assert!(credential(&headers).is_none());
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+
+use entities::{Context as EntityContext, User};
+use services::Services;
+
+use tokio::time::interval;
+
+/// Periodically purges users whose accounts were deleted longer ago than
+/// `Settings::user_purge_grace_period`.
+pub async fn purge_deleted_users(services: Services) {
+    let grace_period = services.settings().user_purge_grace_period;
+    let ctx = EntityContext::new(services);
+    let mut interval = interval(StdDuration::from_secs(60 * 60));
+    loop {
+        interval.tick().await;
+        match User::purge_deleted(grace_period, &ctx).await {
+            Ok(0) => (),
+            Ok(purged) => info!(purged, "purged deleted users"),
+            Err(error) => error!(
+                error = %format!("{:#}", error),
+                "failed to purge deleted users",
+            ),
+        }
+    }
+}
@@ -4,6 +4,7 @@ pub mod entities;
 pub mod env;
 pub mod graph;
 pub mod handlers;
+pub mod jobs;
 pub mod services;
 pub mod util;
 
@@ -1,24 +1,29 @@
 use template_api::entities::BuildInfo;
+use template_api::entities::{Email, Handle, PhoneRegion};
 use template_api::env::load as load_env;
 use template_api::env::var as env_var;
 use template_api::env::var_or as env_var_or;
 use template_api::graph::{Mutation, Query, Subscription};
+use template_api::handlers::data_export_handler;
 use template_api::handlers::graphql_handler;
 use template_api::handlers::graphql_playground_handler;
 use template_api::handlers::GraphQLExtension;
 use template_api::handlers::GraphQLPlaygroundExtension;
+use template_api::jobs::purge_deleted_users;
 use template_api::services::Config as ServicesConfig;
+use template_api::services::{LogMailer, LogSmsSender, MemoryBrokerBackend};
 use template_api::services::{Services, Settings};
 use template_api::util::default;
 
 use std::env::VarError as EnvVarError;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::Context as AnyhowContext;
-use anyhow::Result;
+use anyhow::{ensure, Result};
 
-use http::header::CONTENT_TYPE;
 use http::header::{HeaderValue, InvalidHeaderValue};
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
 use http::Method;
 
 use tower::ServiceBuilder;
@@ -55,8 +60,9 @@ use sentry::ClientOptions as SentryOptions;
 use sentry::IntoDsn as IntoSentryDsn;
 use sentry_tracing::layer as sentry_tracing_layer;
 
+use ::tokio::spawn;
 use bson::doc;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, Duration, FixedOffset};
 use tokio::main as tokio;
 
 #[tokio]
@@ -173,6 +179,70 @@ async fn main() -> Result<()> {
             url.parse()
                 .context("failed to parse justchat-api public URL")?
         })
+        .user_purge_grace_period({
+            let days = env_var_or("TEMPLATE_API_USER_PURGE_DAYS", "30").context(
+                "failed to read environment variable TEMPLATE_API_USER_PURGE_DAYS",
+            )?;
+            let days: i64 =
+                days.parse().context("failed to parse user purge days")?;
+            ensure!(days >= 0, "user purge days must not be negative");
+            Duration::days(days)
+        })
+        .reserved_handles({
+            match env_var("TEMPLATE_API_RESERVED_HANDLES") {
+                Ok(handles) => handles
+                    .split(',')
+                    .map(|handle| handle.trim().to_lowercase())
+                    .filter(|handle| !handle.is_empty())
+                    .collect(),
+                Err(EnvVarError::NotPresent) => Handle::RESERVED
+                    .iter()
+                    .map(|&handle| handle.to_owned())
+                    .collect(),
+                Err(error) => {
+                    return Err(error).context(
+                        "failed to read environment variable \
+                            TEMPLATE_API_RESERVED_HANDLES",
+                    )
+                }
+            }
+        })
+        .default_phone_region({
+            let region = env_var_or("TEMPLATE_API_DEFAULT_PHONE_REGION", "CA")
+                .context(
+                    "failed to read environment variable \
+                        TEMPLATE_API_DEFAULT_PHONE_REGION",
+                )?;
+            let region: PhoneRegion = region
+                .parse()
+                .context("failed to parse default phone region")?;
+            region
+        })
+        .admin_emails({
+            let emails = env_var_or("TEMPLATE_API_ADMIN_EMAILS", "").context(
+                "failed to read environment variable TEMPLATE_API_ADMIN_EMAILS",
+            )?;
+            emails
+                .split(',')
+                .map(str::trim)
+                .filter(|email| !email.is_empty())
+                .map(|email| {
+                    Email::try_from(email.to_owned()).with_context(|| {
+                        format!("failed to parse admin email {}", email)
+                    })
+                })
+                .collect::<Result<_>>()?
+        })
+        .secret({
+            let secret = env_var("TEMPLATE_API_SECRET").context(
+                "failed to read environment variable TEMPLATE_API_SECRET",
+            )?;
+            ensure!(
+                secret.len() >= 32,
+                "secret must be at least 32 characters long"
+            );
+            secret
+        })
         .build();
 
     // Build services
@@ -181,10 +251,16 @@ async fn main() -> Result<()> {
             .database_client(database_client)
             .database(database)
             .settings(settings.clone())
+            .mailer(Arc::new(LogMailer))
+            .sms_sender(Arc::new(LogSmsSender))
+            .broker_backend(Arc::new(MemoryBrokerBackend::default()))
             .build();
         Services::new(config)
     };
 
+    // Start background jobs
+    spawn(purge_deleted_users(services.clone()));
+
     // Build GraphQL schema
     let graphql_schema = {
         let query = Query::default();
@@ -207,7 +283,8 @@ async fn main() -> Result<()> {
             .context("failed to initialize GraphQL playground")?;
     let graphql_layer = CorsLayer::new()
         .allow_methods(vec![Method::GET, Method::POST])
-        .allow_headers(vec![CONTENT_TYPE])
+        .allow_headers(vec![AUTHORIZATION, CONTENT_TYPE])
+        .allow_credentials(true)
         .allow_origin({
             match env_var("JUSTCHAT_API_CORS_ALLOW_ORIGIN") {
                 Ok(origin) => {
@@ -273,12 +350,14 @@ async fn main() -> Result<()> {
                     | MethodFilter::POST,
                 graphql_handler.layer(graphql_layer),
             ),
-        );
+        )
+        .route("/export", on(MethodFilter::GET, data_export_handler));
 
     // Build service
     let service = routes
         .layer({
             ServiceBuilder::new()
+                .layer(AddExtensionLayer::new(services))
                 .layer(AddExtensionLayer::new(graphql_extension))
                 .layer(AddExtensionLayer::new(graphql_playground_extension))
                 .layer(TraceLayer::new_for_http())
@@ -1,13 +1,33 @@
+mod broker;
+mod mailer;
+mod sms;
+mod watcher;
+
+pub use broker::*;
+pub use mailer::*;
+pub use sms::*;
+pub use watcher::*;
+
 use super::*;
 
+use entities::{Email, Handle, PhoneRegion};
+
 use entrust::EntityServices;
 use entrust::{Database, DatabaseClient};
 
+use futures::Stream;
+use futures_util::stream::unfold;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
 #[derive(Debug, Builder)]
 pub struct Config {
     pub database: Database,
     pub database_client: DatabaseClient,
     pub settings: Settings,
+    pub mailer: Arc<dyn Mailer>,
+    pub sms_sender: Arc<dyn SmsSender>,
+    pub broker_backend: Arc<dyn BrokerBackend>,
 }
 
 #[derive(Debug)]
@@ -15,6 +35,10 @@ struct ServicesInner {
     database: Database,
     database_client: DatabaseClient,
     settings: Settings,
+    mailer: Arc<dyn Mailer>,
+    sms_sender: Arc<dyn SmsSender>,
+    broker: Broker,
+    watcher: Watcher,
 }
 
 impl ServicesInner {
@@ -29,6 +53,22 @@ impl ServicesInner {
     fn settings(&self) -> &Settings {
         &self.settings
     }
+
+    fn mailer(&self) -> &dyn Mailer {
+        self.mailer.as_ref()
+    }
+
+    fn sms_sender(&self) -> &dyn SmsSender {
+        self.sms_sender.as_ref()
+    }
+
+    fn broker(&self) -> &Broker {
+        &self.broker
+    }
+
+    fn watcher(&self) -> &Watcher {
+        &self.watcher
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,12 +80,21 @@ impl Services {
             database,
             database_client,
             settings,
+            mailer,
+            sms_sender,
+            broker_backend,
         } = config;
 
+        let broker = Broker::new(broker_backend);
+        let watcher = Watcher::new(database.clone());
         let inner = ServicesInner {
             database,
             database_client,
             settings,
+            mailer,
+            sms_sender,
+            broker,
+            watcher,
         };
         Services(inner.into())
     }
@@ -55,6 +104,10 @@ impl Services {
             pub fn database(&self) -> &Database;
             pub fn database_client(&self) -> &DatabaseClient;
             pub fn settings(&self) -> &Settings;
+            pub fn mailer(&self) -> &dyn Mailer;
+            pub fn sms_sender(&self) -> &dyn SmsSender;
+            pub fn broker(&self) -> &Broker;
+            pub fn watcher(&self) -> &Watcher;
         }
     }
 }
@@ -75,4 +128,80 @@ pub struct Settings {
     pub api_public_url: Url,
     pub web_url: Url,
     pub web_public_url: Url,
+
+    /// How long deleted users are kept around before they are purged.
+    pub user_purge_grace_period: Duration,
+
+    /// Handles that users may not take.
+    pub reserved_handles: Set<String>,
+
+    /// The region assumed for phone numbers that are written without a
+    /// country code, unless the client specifies one.
+    pub default_phone_region: PhoneRegion,
+
+    /// Emails whose users are made admins once they have verified them, so
+    /// that a new deployment can bootstrap its first admin.
+    pub admin_emails: Set<Email>,
+
+    /// A server-side secret for keying hashes of short codes, which would
+    /// otherwise be trivial to reverse.
+    pub secret: String,
+}
+
+impl Settings {
+    /// Resolves `path` against `web_public_url`, for building links that
+    /// point users to the web app.
+    pub fn web_public_url_for(&self, path: &str) -> Result<Url> {
+        join_url(&self.web_public_url, path).context("failed to build web URL")
+    }
+
+    pub fn is_handle_reserved(&self, handle: &Handle) -> bool {
+        self.reserved_handles.contains(handle.as_str())
+    }
+
+    pub fn is_admin_email(&self, email: &Email) -> bool {
+        self.admin_emails.contains(email)
+    }
+
+    /// Resolves `path` against `api_public_url`, for building links that
+    /// point users to this API.
+    pub fn api_public_url_for(&self, path: &str) -> Result<Url> {
+        join_url(&self.api_public_url, path).context("failed to build API URL")
+    }
+}
+
+/// Streams values from a broadcast `receiver`, skipping over any that were
+/// dropped because the receiver fell behind (rather than holding up the
+/// sender).
+fn receiver_stream<T>(receiver: Receiver<T>) -> impl Stream<Item = T>
+where
+    T: Clone + Send + 'static,
+{
+    unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(value) => return Some((value, receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(
+                        target: "template_api::services",
+                        skipped,
+                        "subscriber fell behind; skipped values",
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Resolves `path` against `base`, treating `base` as a directory even if
+/// its path lacks a trailing slash.
+fn join_url(base: &Url, path: &str) -> Result<Url, url::ParseError> {
+    let mut url = base.clone();
+    let base = url.path();
+    if !base.ends_with('/') {
+        let base = base.to_owned() + "/";
+        url.set_path(&base);
+    }
+    url.join(path)
 }
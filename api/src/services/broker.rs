@@ -0,0 +1,128 @@
+use super::*;
+
+use ::bson::{from_bson, to_bson, Bson};
+use futures::Stream;
+use futures_util::stream::{BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use tokio::sync::broadcast::{channel, Sender};
+
+use std::sync::Mutex as SyncMutex;
+
+/// An `Event` is something that happened in the application, which can be
+/// published to a `Broker` for subscribers to react to.
+pub trait Event: Debug + Serialize + DeserializeOwned + Send + 'static {
+    /// The topic that events of this type are published to.
+    const TOPIC: &'static str;
+}
+
+/// A `Broker` routes `Event`s from publishers to subscribers by topic.
+///
+/// Events are delivered through a `BrokerBackend`, which determines whether
+/// they reach subscribers in this process only or across processes.
+#[derive(Debug, Clone)]
+pub struct Broker {
+    backend: Arc<dyn BrokerBackend>,
+}
+
+impl Broker {
+    pub fn new(backend: Arc<dyn BrokerBackend>) -> Self {
+        Broker { backend }
+    }
+
+    /// Publishes `event` to subscribers of its topic.
+    pub async fn publish<E: Event>(&self, event: &E) -> Result<()> {
+        let payload = to_bson(event).context("failed to serialize event")?;
+        self.backend
+            .publish(E::TOPIC, payload)
+            .await
+            .with_context(|| format!("failed to publish to {}", E::TOPIC))
+    }
+
+    /// Subscribes to events of type `E`, starting from the next one
+    /// published.
+    pub fn subscribe<E: Event>(
+        &self,
+    ) -> impl Stream<Item = E> + Send + 'static {
+        self.backend
+            .subscribe(E::TOPIC)
+            .filter_map(|payload| async move {
+                match from_bson::<E>(payload) {
+                    Ok(event) => Some(event),
+                    Err(error) => {
+                        error!(
+                            target: "template_api::services",
+                            topic = E::TOPIC,
+                            %error,
+                            "failed to deserialize event",
+                        );
+                        None
+                    }
+                }
+            })
+    }
+}
+
+/// A `BrokerBackend` transports event payloads between publishers and
+/// subscribers.
+///
+/// Backends must not let slow subscribers hold up publishers; a subscriber
+/// that falls too far behind should miss events instead.
+#[async_trait]
+pub trait BrokerBackend: Debug + Send + Sync {
+    async fn publish(&self, topic: &str, payload: Bson) -> Result<()>;
+    fn subscribe(&self, topic: &str) -> BoxStream<'static, Bson>;
+}
+
+/// A `MemoryBrokerBackend` delivers events to subscribers within this
+/// process, which is enough for a single-instance deployment.
+///
+/// Each subscriber buffers up to `capacity` events; beyond that, the oldest
+/// events are dropped for that subscriber (e.g. a WebSocket client on a slow
+/// connection) without affecting anyone else.
+#[derive(Debug)]
+pub struct MemoryBrokerBackend {
+    capacity: usize,
+    topics: SyncMutex<Map<String, Sender<Bson>>>,
+}
+
+impl MemoryBrokerBackend {
+    pub fn new(capacity: usize) -> Self {
+        MemoryBrokerBackend {
+            capacity,
+            topics: default(),
+        }
+    }
+}
+
+impl Default for MemoryBrokerBackend {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+#[async_trait]
+impl BrokerBackend for MemoryBrokerBackend {
+    async fn publish(&self, topic: &str, payload: Bson) -> Result<()> {
+        let mut topics = self.topics.lock().unwrap();
+        if let Some(sender) = topics.get(topic) {
+            // Forget topics that nobody is subscribed to anymore
+            if sender.send(payload).is_err() {
+                topics.remove(topic);
+            }
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self, topic: &str) -> BoxStream<'static, Bson> {
+        let mut topics = self.topics.lock().unwrap();
+        let receiver = match topics.get(topic) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = channel(self.capacity);
+                topics.insert(topic.to_owned(), sender);
+                receiver
+            }
+        };
+        receiver_stream(receiver).boxed()
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+use entities::Email;
+
+/// A `Mail` is a plaintext email message.
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: Email,
+    pub subject: String,
+    pub body: String,
+}
+
+/// A `Mailer` delivers `Mail` to its recipient.
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// A `LogMailer` writes mail to the log instead of delivering it, which is
+/// useful during local development.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        let Mail { to, subject, body } = mail;
+        info!(target: "template_api::mailer", %to, %subject, "{}", body);
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use super::*;
+
+use entities::Phone;
+
+/// An `Sms` is a text message.
+#[derive(Debug, Clone)]
+pub struct Sms {
+    pub to: Phone,
+    pub body: String,
+}
+
+/// An `SmsSender` delivers an `Sms` to its recipient.
+#[async_trait]
+pub trait SmsSender: Debug + Send + Sync {
+    async fn send(&self, sms: Sms) -> Result<()>;
+}
+
+/// A `LogSmsSender` writes text messages to the log instead of delivering
+/// them, which is useful during local development.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogSmsSender;
+
+#[async_trait]
+impl SmsSender for LogSmsSender {
+    async fn send(&self, sms: Sms) -> Result<()> {
+        let Sms { to, body } = sms;
+        info!(target: "template_api::sms", %to, "{}", body);
+        Ok(())
+    }
+}
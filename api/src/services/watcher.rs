@@ -0,0 +1,204 @@
+use super::*;
+
+use entrust::{Database, Entity};
+
+use mongodb::change_stream::event::ResumeToken;
+use mongodb::change_stream::event::{ChangeStreamEvent, OperationType};
+use mongodb::options::ChangeStreamOptions;
+
+use ::bson::{Bson, Document};
+use futures::Stream;
+use futures_util::stream::StreamExt;
+use tokio::select;
+use tokio::sync::broadcast::{channel, Sender};
+use tokio::time::{interval, sleep};
+
+use std::sync::Mutex as SyncMutex;
+
+/// A `ChangeEvent` describes a change to a document in a collection.
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub kind: ChangeKind,
+
+    /// The `_id` of the changed document.
+    pub id: Bson,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Replace,
+    Delete,
+}
+
+/// A `Watcher` watches collections for changes using MongoDB change streams,
+/// and fans them out to subscribers.
+///
+/// Each collection is watched by a single change stream, which is opened
+/// when it gains its first subscriber and closed once it has none left.
+#[derive(Debug)]
+pub struct Watcher {
+    database: Database,
+    channels: Arc<SyncMutex<Map<String, Sender<ChangeEvent>>>>,
+}
+
+impl Watcher {
+    /// How many events may be buffered for a subscriber before it starts
+    /// missing them.
+    const CAPACITY: usize = 256;
+
+    /// How often to check whether a collection's subscribers have all gone,
+    /// so that its change stream is closed even if the collection is idle.
+    const IDLE_CHECK_INTERVAL: StdDuration = StdDuration::from_secs(30);
+
+    pub fn new(database: Database) -> Self {
+        Watcher {
+            database,
+            channels: default(),
+        }
+    }
+
+    /// Subscribes to changes to entities of type `T`.
+    pub fn watch<T: Entity>(
+        &self,
+    ) -> impl Stream<Item = ChangeEvent> + Send + 'static {
+        let collection = collection_name(T::NAME);
+        self.watch_collection(&collection)
+    }
+
+    /// Subscribes to changes to documents in `collection`.
+    pub fn watch_collection(
+        &self,
+        collection: &str,
+    ) -> impl Stream<Item = ChangeEvent> + Send + 'static {
+        let receiver = {
+            let mut channels = self.channels.lock().unwrap();
+            match channels.get(collection) {
+                Some(sender) => sender.subscribe(),
+                None => {
+                    let (sender, receiver) = channel(Self::CAPACITY);
+                    channels.insert(collection.to_owned(), sender.clone());
+                    spawn(forward_changes(
+                        self.database.clone(),
+                        collection.to_owned(),
+                        sender,
+                        self.channels.clone(),
+                    ));
+                    receiver
+                }
+            }
+        };
+        receiver_stream(receiver)
+    }
+}
+
+/// Forwards changes to `collection` to `sender`, until it has no receivers
+/// left.
+///
+/// If the change stream fails, it is reopened where it left off.
+async fn forward_changes(
+    database: Database,
+    collection: String,
+    sender: Sender<ChangeEvent>,
+    channels: Arc<SyncMutex<Map<String, Sender<ChangeEvent>>>>,
+) {
+    let mut resume_token: Option<ResumeToken> = None;
+    let mut idle_check = interval(Watcher::IDLE_CHECK_INTERVAL);
+    loop {
+        if remove_if_idle(&collection, &sender, &channels) {
+            return;
+        }
+        let options = ChangeStreamOptions::builder()
+            .resume_after(resume_token.clone())
+            .build();
+        let result = database
+            .collection::<Document>(&collection)
+            .watch(None, options)
+            .await;
+        let mut stream = match result {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!(
+                    target: "template_api::services",
+                    %collection,
+                    %error,
+                    "failed to open change stream",
+                );
+                sleep(StdDuration::from_secs(1)).await;
+                continue;
+            }
+        };
+        loop {
+            let result = select! {
+                result = stream.next() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+                _ = idle_check.tick() => {
+                    if remove_if_idle(&collection, &sender, &channels) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            let event = match result {
+                Ok(event) => event,
+                Err(error) => {
+                    error!(
+                        target: "template_api::services",
+                        %collection,
+                        %error,
+                        "failed to read change stream",
+                    );
+                    break;
+                }
+            };
+            resume_token = stream.resume_token();
+            let event = match change_event(event) {
+                Some(event) => event,
+                None => continue,
+            };
+
+            // Stop watching once there's nobody left to notify
+            if sender.send(event).is_err()
+                && remove_if_idle(&collection, &sender, &channels)
+            {
+                return;
+            }
+        }
+        sleep(StdDuration::from_secs(1)).await;
+    }
+}
+
+/// Stops fanning out changes to `collection` if `sender` has no receivers
+/// left, returning whether it was removed.
+///
+/// The check is made while holding the lock on `channels`, so that nobody
+/// can subscribe to `sender` in the meantime.
+fn remove_if_idle(
+    collection: &str,
+    sender: &Sender<ChangeEvent>,
+    channels: &SyncMutex<Map<String, Sender<ChangeEvent>>>,
+) -> bool {
+    let mut channels = channels.lock().unwrap();
+    if sender.receiver_count() > 0 {
+        return false;
+    }
+    channels.remove(collection);
+    true
+}
+
+fn change_event(event: ChangeStreamEvent<Document>) -> Option<ChangeEvent> {
+    use OperationType::*;
+    let kind = match event.operation_type {
+        Insert => ChangeKind::Insert,
+        Update => ChangeKind::Update,
+        Replace => ChangeKind::Replace,
+        Delete => ChangeKind::Delete,
+        _ => return None,
+    };
+    let id = event.document_key?.get("_id")?.clone();
+    let event = ChangeEvent { kind, id };
+    Some(event)
+}
@@ -7,3 +7,20 @@ pub fn default<T: Default>() -> T {
 pub fn now() -> DateTime {
     Utc::now()
 }
+
+/// Determines the name of the collection that stores entities named `name`,
+/// i.e. `user_token` for `UserToken`.
+pub fn collection_name(name: &str) -> String {
+    let mut collection = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                collection.push('_');
+            }
+            collection.extend(c.to_lowercase());
+        } else {
+            collection.push(c);
+        }
+    }
+    collection
+}
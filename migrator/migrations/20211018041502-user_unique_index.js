@@ -0,0 +1,19 @@
+module.exports = {
+  async up(db) {
+    const user = db.collection("user");
+    await user.dropIndex("email");
+    await user.dropIndex("phone");
+    await user.createIndex({ handle: 1 }, { name: "handle", unique: true });
+    await user.createIndex({ email: 1 }, { name: "email", unique: true });
+    await user.createIndex({ phone: 1 }, { name: "phone", unique: true });
+  },
+
+  async down(db) {
+    const user = db.collection("user");
+    await user.dropIndex("handle");
+    await user.dropIndex("email");
+    await user.dropIndex("phone");
+    await user.createIndex({ email: 1 }, { name: "email" });
+    await user.createIndex({ phone: 1 }, { name: "phone" });
+  },
+};
@@ -0,0 +1,18 @@
+module.exports = {
+  async up(db) {
+    const session = db.collection("session");
+    await session.createIndex({ token: 1 }, { name: "token", unique: true });
+    await session.createIndex({ user: 1 }, { name: "user" });
+    await session.createIndex(
+      { expires_at: 1 },
+      { name: "expires_at", expireAfterSeconds: 0 },
+    );
+  },
+
+  async down(db) {
+    const session = db.collection("session");
+    await session.dropIndex("token");
+    await session.dropIndex("user");
+    await session.dropIndex("expires_at");
+  },
+};
@@ -0,0 +1,18 @@
+module.exports = {
+  async up(db) {
+    const userToken = db.collection("user_token");
+    await userToken.createIndex({ token: 1 }, { name: "token", unique: true });
+    await userToken.createIndex({ user: 1, kind: 1 }, { name: "user_kind" });
+    await userToken.createIndex(
+      { expires_at: 1 },
+      { name: "expires_at", expireAfterSeconds: 0 },
+    );
+  },
+
+  async down(db) {
+    const userToken = db.collection("user_token");
+    await userToken.dropIndex("token");
+    await userToken.dropIndex("user_kind");
+    await userToken.dropIndex("expires_at");
+  },
+};
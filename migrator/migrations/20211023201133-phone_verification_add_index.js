@@ -0,0 +1,16 @@
+module.exports = {
+  async up(db) {
+    const phoneVerification = db.collection("phone_verification");
+    await phoneVerification.createIndex({ user: 1 }, { name: "user" });
+    await phoneVerification.createIndex(
+      { expires_at: 1 },
+      { name: "expires_at", expireAfterSeconds: 0 },
+    );
+  },
+
+  async down(db) {
+    const phoneVerification = db.collection("phone_verification");
+    await phoneVerification.dropIndex("user");
+    await phoneVerification.dropIndex("expires_at");
+  },
+};
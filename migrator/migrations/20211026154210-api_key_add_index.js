@@ -0,0 +1,13 @@
+module.exports = {
+  async up(db) {
+    const apiKey = db.collection("api_key");
+    await apiKey.createIndex({ key: 1 }, { name: "key", unique: true });
+    await apiKey.createIndex({ user: 1 }, { name: "user" });
+  },
+
+  async down(db) {
+    const apiKey = db.collection("api_key");
+    await apiKey.dropIndex("key");
+    await apiKey.dropIndex("user");
+  },
+};
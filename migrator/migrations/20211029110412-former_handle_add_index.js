@@ -0,0 +1,18 @@
+module.exports = {
+  async up(db) {
+    const formerHandle = db.collection("former_handle");
+    await formerHandle.createIndex({ handle: 1 }, { name: "handle" });
+    await formerHandle.createIndex({ user: 1 }, { name: "user" });
+    await formerHandle.createIndex(
+      { reserved_until: 1 },
+      { name: "reserved_until", expireAfterSeconds: 0 },
+    );
+  },
+
+  async down(db) {
+    const formerHandle = db.collection("former_handle");
+    await formerHandle.dropIndex("handle");
+    await formerHandle.dropIndex("user");
+    await formerHandle.dropIndex("reserved_until");
+  },
+};
@@ -0,0 +1,11 @@
+module.exports = {
+  async up(db) {
+    const user = db.collection("user");
+    await user.createIndex({ name: 1, _id: 1 }, { name: "name" });
+  },
+
+  async down(db) {
+    const user = db.collection("user");
+    await user.dropIndex("name");
+  },
+};
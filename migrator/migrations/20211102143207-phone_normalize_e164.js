@@ -0,0 +1,53 @@
+// Phones used to be stored as formatted for display (i.e. `+1 416-555-0123`),
+// which lookups and the unique index on `user.phone` can't match against
+// E.164 (i.e. `+14165550123`). Rewrite them all in E.164.
+//
+// Phones were always stored with their country code, so normalizing them is
+// a matter of dropping everything but the digits.
+
+const NON_E164 = { $type: "string", $not: /^\+\d+$/ };
+
+async function normalizePhones(collection) {
+  const problems = [];
+  const cursor = collection.find(
+    { phone: NON_E164 },
+    { projection: { phone: 1 } },
+  );
+  while (await cursor.hasNext()) {
+    const { _id, phone } = await cursor.next();
+    if (!phone.startsWith("+")) {
+      problems.push(`${_id}: ${phone} (missing country code)`);
+      continue;
+    }
+    const normalized = "+" + phone.replace(/\D/g, "");
+    try {
+      await collection.updateOne({ _id }, { $set: { phone: normalized } });
+    } catch (error) {
+      if (error.code !== 11000) {
+        throw error;
+      }
+      problems.push(`${_id}: ${phone} (${normalized} is already taken)`);
+    }
+  }
+  return problems;
+}
+
+module.exports = {
+  async up(db) {
+    const problems = [
+      ...(await normalizePhones(db.collection("user"))),
+      ...(await normalizePhones(db.collection("phone_verification"))),
+    ];
+    if (problems.length > 0) {
+      // Everything else was normalized, so this can be re-run once these
+      // have been resolved by hand
+      throw new Error(
+        "failed to normalize some phones:\n" + problems.join("\n"),
+      );
+    }
+  },
+
+  async down() {
+    // Display formats can't be recovered, and E.164 phones remain valid
+  },
+};